@@ -1,74 +1,70 @@
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::Response;
+use bytes::Bytes;
+
+use super::get_items::{visible_item, PreviewParams};
+use crate::api::conditional::{CachePolicy, Validators};
+use crate::api::error::ApiError;
+use crate::api::range::{ranged_body, requested_range};
 use crate::app::AppState;
-use axum::body::Body;
-use axum::extract::{Path, State};
-use axum::http::{header, StatusCode};
-use axum::response::{IntoResponse, Response};
-use reqwest::Client;
+use crate::cache::ResourceKind;
+use crate::content::{
+    anchor_headings, highlight_code_blocks, sanitize_html, text_only, ContentSourceError,
+};
+use crate::slug::Slug;
 
 pub async fn handler(
     State(state): State<AppState>,
-    Path(name): Path<String>,
-) -> Result<impl IntoResponse, GetItemsError> {
-    let leaky_url = state.leaky_url.clone();
-    let client = Client::new();
-    let url = leaky_url
-        .join(&format!("/writing/{}?html=true", name))
-        .map_err(|_| GetItemsError::UrlJoinError)?;
+    slug: Slug,
+    Query(params): Query<PreviewParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let name = slug.into_string();
+    let visible = visible_item(&state, &name, &params)
+        .await?
+        .ok_or(ApiError::NotFound("Writing"))?;
 
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .map_err(|_| GetItemsError::RequestFailed)?;
+    let bytes = post_html(&state, name).await?;
 
-    if response.status() == StatusCode::NOT_FOUND {
-        return Err(GetItemsError::WritingNotFound);
-    }
+    let response = Response::builder().header(header::CONTENT_TYPE, "text/html; charset=utf-8");
 
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|_| GetItemsError::ResponseReadError)?;
+    let validators = Validators::new(
+        visible.cache_policy(CachePolicy::Post),
+        &bytes,
+        Some(visible.item.updated_at),
+    );
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified());
+    }
 
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
-        .body(Body::from(bytes))
-        .map_err(|_| GetItemsError::ResponseBuildError)?)
+    let mut response = ranged_body(response, requested_range(&headers, &validators), bytes)?;
+    validators.apply(response.headers_mut());
+    Ok(response)
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum GetItemsError {
-    #[error("Failed to construct URL")]
-    UrlJoinError,
-    #[error("Failed to send request")]
-    RequestFailed,
-    #[error("Writing not found")]
-    WritingNotFound,
-    #[error("Failed to read response body")]
-    ResponseReadError,
-    #[error("Failed to build response")]
-    ResponseBuildError,
-}
+/// The rendered HTML of the post called `name`, sanitized, with its code blocks highlighted and its
+/// headings anchored, through the content cache.
+pub(crate) async fn post_html(state: &AppState, name: String) -> Result<Bytes, ContentSourceError> {
+    let content_source = state.content_source.clone();
+    let key = format!("/writing/{}?html=true", name);
+    state
+        .content_cache
+        .bytes(ResourceKind::Html, &key, || async move {
+            let html = content_source.get_post(&name).await?;
 
-impl IntoResponse for GetItemsError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            GetItemsError::UrlJoinError => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
-            }
-            GetItemsError::RequestFailed => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch writing")
-            }
-            GetItemsError::WritingNotFound => (StatusCode::NOT_FOUND, "Writing not found"),
-            GetItemsError::ResponseReadError => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read response")
-            }
-            GetItemsError::ResponseBuildError => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to build response",
-            ),
-        };
-        (status, error_message).into_response()
-    }
+            // Post-processing is CPU bound, so it's kept off the async workers. The upstream HTML
+            // is sanitized before anything of our own is added to it.
+            let processed = tokio::task::spawn_blocking({
+                let html = html.clone();
+                let name = name.clone();
+                move || anchor_headings(highlight_code_blocks(sanitize_html(&name, html)))
+            })
+            .await;
+            Ok(processed.unwrap_or_else(|err| {
+                tracing::error!("post-processing {name} panicked, serving its text only: {err}");
+                text_only(&html)
+            }))
+        })
+        .await
 }
@@ -0,0 +1,30 @@
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+
+use super::get_items::{fill_stats, visible_item, PreviewParams};
+use crate::api::conditional::{respond_json, CachePolicy};
+use crate::api::error::ApiError;
+use crate::app::AppState;
+use crate::slug::Slug;
+
+/// A single post as the listing describes it. Unpublished posts can be fetched with a preview
+/// token, which is how previews are rendered when the listing leaves them out.
+pub async fn handler(
+    State(state): State<AppState>,
+    slug: Slug,
+    Query(params): Query<PreviewParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let mut visible = visible_item(&state, slug.as_str(), &params)
+        .await?
+        .ok_or(ApiError::NotFound("Writing"))?;
+    fill_stats(&state, std::slice::from_mut(&mut visible.item)).await;
+
+    respond_json(
+        visible.cache_policy(CachePolicy::Listing),
+        &headers,
+        &visible.item,
+        Some(visible.item.updated_at),
+    )
+}
@@ -1,80 +1,392 @@
-use axum::extract::{Json, State};
-use axum::http::StatusCode;
-use axum::response::{IntoResponse, Response};
-use reqwest::Client;
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use time::OffsetDateTime;
 
+use super::get_content::post_html;
+use crate::api::conditional::{respond_json, CachePolicy};
+use crate::api::error::ApiError;
+use crate::api::pagination::{paginate, ListParams, Listed};
 use crate::app::AppState;
+use crate::stats::PostStats;
+
+/// How many posts' HTML is fetched at once to fill in their stats.
+const STATS_CONCURRENCY: usize = 4;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct ItemMetadata {
     description: String,
     title: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    series: Option<String>,
+    #[serde(default)]
+    series_order: Option<u32>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    publish_at: Option<OffsetDateTime>,
 }
 
+/// Timestamps arrive in `time`'s compact serde form:
+/// `[year, ordinal, hour, minute, second, nanosecond, offset_hours, offset_minutes, offset_seconds]`.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct ItemData {
-    created_at: [i64; 9],
-    updated_at: [i64; 9],
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
     metadata: ItemMetadata,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct Item {
-    name: String,
-    title: String,
-    description: String,
-    created_at: OffsetDateTime,
+pub(crate) struct Item {
+    pub(crate) name: String,
+    pub(crate) title: String,
+    pub(crate) description: String,
+    pub(crate) tags: Vec<String>,
+    /// The series the post is a part of, normalized like a tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) series: Option<String>,
+    /// Where the post falls in its series. Parts without one follow those with, by date.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) series_order: Option<u32>,
+    /// Drafts are never listed, only previewed.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) draft: bool,
+    /// When the post goes out. Until then it's only previewed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) publish_at: Option<OffsetDateTime>,
+    pub(crate) created_at: OffsetDateTime,
+    pub(crate) updated_at: OffsetDateTime,
+    /// Only filled in for the posts a listing page returns, and left out if the post's HTML
+    /// couldn't be fetched.
+    #[serde(flatten)]
+    pub(crate) stats: Option<PostStats>,
+}
+
+impl Item {
+    /// Whether the post is out for everyone to read at `now`.
+    pub(crate) fn is_published(&self, now: OffsetDateTime) -> bool {
+        !self.draft && self.publish_at.map_or(true, |publish_at| publish_at <= now)
+    }
+
+    /// When the post last changed for anyone listing it: its last update, or when it went out if
+    /// that's later. A scheduled post going out has to move a listing's `Last-Modified` as well.
+    pub(crate) fn changed_at(&self) -> OffsetDateTime {
+        self.publish_at.map_or(self.updated_at, |publish_at| {
+            publish_at.max(self.updated_at)
+        })
+    }
+}
+
+/// A post a reader may see.
+pub(super) struct VisibleItem {
+    pub(super) item: Item,
+    /// Whether it's only visible through a preview, and so mustn't be cached publicly.
+    pub(super) previewed: bool,
 }
 
-pub async fn handler(State(state): State<AppState>) -> Result<impl IntoResponse, GetItemsError> {
-    let leaky_url = state.leaky_url.clone();
-    let client = Client::new();
-    let response: Vec<Value> = client
-        .get(leaky_url.join("/writing").unwrap())
-        .send()
-        .await
-        .unwrap()
-        .json()
-        .await
-        .unwrap();
+impl VisibleItem {
+    pub(super) fn cache_policy(&self, published: CachePolicy) -> CachePolicy {
+        if self.previewed {
+            CachePolicy::Preview
+        } else {
+            published
+        }
+    }
+}
 
-    let mut posts: Vec<Item> = response.iter().filter_map(parse_item_data).collect();
+/// A token letting an unpublished post be read.
+#[derive(Debug, Deserialize)]
+pub struct PreviewParams {
+    preview: Option<String>,
+}
 
-    posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-    Ok((StatusCode::OK, Json(posts)))
+/// Only list posts carrying `tag`.
+#[derive(Debug, Deserialize)]
+pub struct TagFilter {
+    tag: Option<String>,
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum GetItemsError {}
+impl Listed for Item {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn created_at(&self) -> OffsetDateTime {
+        self.created_at
+    }
 
-impl IntoResponse for GetItemsError {
-    fn into_response(self) -> Response {
-        match self {}
+    fn updated_at(&self) -> OffsetDateTime {
+        self.updated_at
     }
 }
 
-use time::Date;
+pub async fn handler(
+    State(state): State<AppState>,
+    Query(params): Query<ListParams>,
+    Query(filter): Query<TagFilter>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let mut posts = list_items(&state).await?;
+    if let Some(tag) = filter.tag.as_deref().and_then(normalize_tag) {
+        posts.retain(|post| post.tags.contains(&tag));
+    }
+
+    let updated_at = posts.iter().map(Item::changed_at).max();
+    let mut page = paginate(posts, &params)?;
+    fill_stats(&state, &mut page.items).await;
 
-fn parse_item_data(value: &Value) -> Option<Item> {
-    let v_array = value.as_array().unwrap();
-    let v_name: String = v_array.get(0).unwrap().as_str().unwrap().to_string();
-    let v_values = v_array.get(1);
-    v_values
-        .and_then(|values| values.get(1))
-        .and_then(|data| serde_json::from_value::<ItemData>(data.clone()).ok())
-        .map(|data| {
-            let year = data.created_at[0] as i32;
-            let day_of_year = data.created_at[1] as u16;
-            let date = Date::from_ordinal_date(year, day_of_year).unwrap();
-
-            Item {
-                name: v_name,
-                title: data.metadata.title,
-                description: data.metadata.description,
-                created_at: date.with_hms(0, 0, 0).unwrap().assume_utc(),
+    respond_json(CachePolicy::Listing, &headers, &page, updated_at)
+}
+
+/// Every published, well-formed post in the writing listing, in listing order. Drafts and posts
+/// scheduled for later are left out, which keeps them out of every listing, feed and sitemap.
+pub(crate) async fn list_items(state: &AppState) -> Result<Vec<Item>, ApiError> {
+    let now = OffsetDateTime::now_utc();
+    let mut posts = list_all_items(state).await?;
+    posts.retain(|post| post.is_published(now));
+    Ok(posts)
+}
+
+/// Every well-formed post in the writing listing, published or not, in listing order.
+async fn list_all_items(state: &AppState) -> Result<Vec<Item>, ApiError> {
+    let content_source = state.content_source.clone();
+    let response = state
+        .content_cache
+        .listing(
+            "/writing",
+            || async move { content_source.list_posts().await },
+        )
+        .await?;
+
+    let posts = response
+        .iter()
+        .filter_map(|value| match parse_item_data(value) {
+            Ok(item) => Some(item),
+            Err(err) => {
+                tracing::warn!("skipping writing entry: {err}");
+                None
             }
         })
+        .collect();
+
+    Ok(posts)
+}
+
+/// The post called `name`, if it's published or `preview` is a valid preview token for it. Posts
+/// missing from the listing are never visible, so a post can't be read before it's listed.
+pub(super) async fn visible_item(
+    state: &AppState,
+    name: &str,
+    params: &PreviewParams,
+) -> Result<Option<VisibleItem>, ApiError> {
+    let now = OffsetDateTime::now_utc();
+    let Some(item) = list_all_items(state)
+        .await?
+        .into_iter()
+        .find(|post| post.name == name)
+    else {
+        return Ok(None);
+    };
+    if item.is_published(now) {
+        return Ok(Some(VisibleItem {
+            item,
+            previewed: false,
+        }));
+    }
+
+    let (Some(key), Some(token)) = (&state.preview_key, &params.preview) else {
+        return Ok(None);
+    };
+    match key.verify(name, token, now) {
+        Ok(()) => Ok(Some(VisibleItem {
+            item,
+            previewed: true,
+        })),
+        Err(err) => {
+            tracing::info!("refusing to preview {name}: {err}");
+            Ok(None)
+        }
+    }
+}
+
+/// Fill in the stats of `posts`, fetching the HTML of any that haven't been seen at their current
+/// version.
+pub(super) async fn fill_stats(state: &AppState, posts: &mut [Item]) {
+    let versions: Vec<(String, OffsetDateTime)> = posts
+        .iter()
+        .map(|post| (post.name.clone(), post.updated_at))
+        .collect();
+
+    let stats = stream::iter(versions)
+        .map(|(name, updated_at)| async move {
+            let fetch_html = || post_html(state, name.clone());
+            match state.post_stats.get(&name, updated_at, fetch_html).await {
+                Ok(stats) => Some(stats),
+                Err(err) => {
+                    tracing::warn!("failed to compute stats of {name}: {err}");
+                    None
+                }
+            }
+        })
+        .buffered(STATS_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    for (post, stats) in posts.iter_mut().zip(stats) {
+        post.stats = stats;
+    }
+}
+
+/// Tags are matched case-insensitively, with runs of whitespace standing in for a single `-`, so
+/// `Machine Learning` and `machine-learning` are the same tag and are safe to put in a URL.
+fn dedup_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags.iter().filter_map(|tag| normalize_tag(tag)) {
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+pub(super) fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+
+    (!tag.is_empty()).then_some(tag)
+}
+
+fn parse_item_data(value: &Value) -> Result<Item, ApiError> {
+    let malformed = |name: &str, reason: &str| ApiError::MalformedEntry {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let v_name = value
+        .get(0)
+        .and_then(Value::as_str)
+        .ok_or_else(|| malformed("<unnamed>", "entry is not a [name, [cid, data]] pair"))?;
+    let v_data = value
+        .get(1)
+        .and_then(|values| values.get(1))
+        .ok_or_else(|| malformed(v_name, "entry is missing its data"))?;
+    let data = serde_json::from_value::<ItemData>(v_data.clone())
+        .map_err(|err| malformed(v_name, &err.to_string()))?;
+
+    Ok(Item {
+        name: v_name.to_string(),
+        title: data.metadata.title,
+        description: data.metadata.description,
+        tags: dedup_tags(&data.metadata.tags),
+        series: data.metadata.series.as_deref().and_then(normalize_tag),
+        series_order: data.metadata.series_order,
+        draft: data.metadata.draft,
+        publish_at: data.metadata.publish_at,
+        created_at: data.created_at,
+        updated_at: data.updated_at,
+        stats: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_item_data() {
+        let value = serde_json::json!([
+            "hello-world",
+            [
+                "bafy",
+                {
+                    "created_at": [2024, 100, 13, 45, 30, 500, -5, 0, 0],
+                    "updated_at": [2024, 102, 9, 0, 0, 0, 0, 0, 0],
+                    "metadata": {
+                        "title": "Hello",
+                        "description": "World",
+                        "tags": ["Rust", "machine  learning", "rust", " "],
+                        "series": "Learning Rust",
+                        "series_order": 2,
+                        "draft": true,
+                        "publish_at": "2024-05-01T09:00:00Z"
+                    }
+                }
+            ]
+        ]);
+
+        let item = parse_item_data(&value).unwrap();
+        assert_eq!(item.name, "hello-world");
+        assert_eq!(item.tags, ["rust", "machine-learning"]);
+        assert_eq!(item.series.as_deref(), Some("learning-rust"));
+        assert_eq!(item.series_order, Some(2));
+        assert!(item.draft);
+        assert_eq!(item.publish_at.unwrap().unix_timestamp(), 1_714_554_000);
+        assert_eq!(item.created_at.ordinal(), 100);
+        assert_eq!(
+            item.created_at.time(),
+            time::Time::from_hms_nano(13, 45, 30, 500).unwrap()
+        );
+        assert_eq!(item.created_at.offset().whole_hours(), -5);
+        assert_eq!(item.updated_at.ordinal(), 102);
+    }
+
+    #[test]
+    fn test_parse_item_data_malformed() {
+        let not_a_pair = serde_json::json!({"name": "hello-world"});
+        assert!(matches!(
+            parse_item_data(&not_a_pair),
+            Err(ApiError::MalformedEntry { .. })
+        ));
+
+        let bad_date = serde_json::json!([
+            "hello-world",
+            [
+                "bafy",
+                {
+                    "created_at": [2024, 400, 0, 0, 0, 0, 0, 0, 0],
+                    "updated_at": [2024, 400, 0, 0, 0, 0, 0, 0, 0],
+                    "metadata": {"title": "Hello", "description": "World"}
+                }
+            ]
+        ]);
+        match parse_item_data(&bad_date) {
+            Err(ApiError::MalformedEntry { name, .. }) => assert_eq!(name, "hello-world"),
+            other => panic!("expected a malformed entry, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_is_published() {
+        let value = serde_json::json!([
+            "hello-world",
+            [
+                "bafy",
+                {
+                    "created_at": [2024, 100, 0, 0, 0, 0, 0, 0, 0],
+                    "updated_at": [2024, 100, 0, 0, 0, 0, 0, 0, 0],
+                    "metadata": {"title": "Hello", "description": "World"}
+                }
+            ]
+        ]);
+        let mut item = parse_item_data(&value).unwrap();
+        let now = OffsetDateTime::now_utc();
+        assert!(item.is_published(now));
+
+        item.publish_at = Some(now + time::Duration::hours(1));
+        assert!(!item.is_published(now));
+        assert!(item.is_published(now + time::Duration::hours(2)));
+        assert_eq!(item.changed_at(), now + time::Duration::hours(1));
+
+        item.publish_at = None;
+        item.draft = true;
+        assert!(!item.is_published(now));
+    }
 }
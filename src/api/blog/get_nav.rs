@@ -0,0 +1,77 @@
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use serde::Serialize;
+
+use super::get_items::{list_items, visible_item, Item, PreviewParams};
+use super::get_series::{series, Series};
+use crate::api::conditional::{respond_json, CachePolicy};
+use crate::api::error::ApiError;
+use crate::app::AppState;
+use crate::slug::Slug;
+
+/// A post to link to.
+#[derive(Debug, Serialize)]
+struct PostLink {
+    name: String,
+    title: String,
+}
+
+impl From<&Item> for PostLink {
+    fn from(post: &Item) -> Self {
+        Self {
+            name: post.name.clone(),
+            title: post.title.clone(),
+        }
+    }
+}
+
+/// Where to go from a post.
+#[derive(Debug, Serialize)]
+struct PostNav {
+    /// The post published just before this one.
+    previous: Option<PostLink>,
+    /// The post published just after this one.
+    next: Option<PostLink>,
+    /// The series the post is a part of, if any.
+    series: Option<Series>,
+}
+
+/// The posts published either side of this one, and the parts of its series. A previewed post is
+/// placed among the published ones as if it were out.
+pub async fn handler(
+    State(state): State<AppState>,
+    slug: Slug,
+    Query(params): Query<PreviewParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let visible = visible_item(&state, slug.as_str(), &params)
+        .await?
+        .ok_or(ApiError::NotFound("Writing"))?;
+    let mut posts = list_items(&state).await?;
+    if visible.previewed {
+        posts.push(visible.item.clone());
+    }
+    posts.sort_by(|a, b| (a.created_at, &a.name).cmp(&(b.created_at, &b.name)));
+
+    let position = posts
+        .iter()
+        .position(|post| post.name == slug.as_str())
+        .ok_or(ApiError::NotFound("Writing"))?;
+    let post = &posts[position];
+    let nav = PostNav {
+        previous: position
+            .checked_sub(1)
+            .map(|previous| PostLink::from(&posts[previous])),
+        next: posts.get(position + 1).map(PostLink::from),
+        series: post.series.as_deref().and_then(|name| series(&posts, name)),
+    };
+
+    let updated_at = posts.iter().map(Item::changed_at).max();
+    respond_json(
+        visible.cache_policy(CachePolicy::Listing),
+        &headers,
+        &nav,
+        updated_at,
+    )
+}
@@ -0,0 +1,59 @@
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use serde::Deserialize;
+
+use super::get_content::post_html;
+use super::get_items::{visible_item, PreviewParams};
+use crate::api::conditional::{respond_json, CachePolicy};
+use crate::api::error::ApiError;
+use crate::api::search::{current_index, document};
+use crate::app::AppState;
+use crate::content::html_to_text;
+use crate::slug::Slug;
+
+/// How many related posts are returned when the client doesn't say.
+const DEFAULT_LIMIT: usize = 3;
+
+/// The most related posts a client may ask for.
+const MAX_LIMIT: usize = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct RelatedParams {
+    limit: Option<usize>,
+}
+
+/// The posts most like this one, by the tags they share and how much their text overlaps, most
+/// related first. A previewed post isn't in the search index, so it's ranked against an index
+/// that includes it.
+pub async fn handler(
+    State(state): State<AppState>,
+    slug: Slug,
+    Query(params): Query<RelatedParams>,
+    Query(preview): Query<PreviewParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let visible = visible_item(&state, slug.as_str(), &preview)
+        .await?
+        .ok_or(ApiError::NotFound("Writing"))?;
+
+    let index = current_index(&state).await?;
+    let related = if visible.previewed {
+        let html = post_html(&state, slug.to_string()).await?;
+        let body = html_to_text(&String::from_utf8_lossy(&html));
+        index
+            .including(document(visible.item.clone()), body)
+            .related(slug.as_str(), limit)
+    } else {
+        index.related(slug.as_str(), limit)
+    }
+    .ok_or(ApiError::NotFound("Writing"))?;
+
+    respond_json(
+        visible.cache_policy(CachePolicy::Listing),
+        &headers,
+        &related,
+        None,
+    )
+}
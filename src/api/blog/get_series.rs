@@ -0,0 +1,127 @@
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use super::get_items::{list_items, normalize_tag, Item};
+use crate::api::conditional::{respond_json, CachePolicy};
+use crate::api::error::ApiError;
+use crate::app::AppState;
+
+/// A post in a series.
+#[derive(Debug, Serialize)]
+pub(super) struct Part {
+    name: String,
+    title: String,
+    description: String,
+    series_order: Option<u32>,
+    created_at: OffsetDateTime,
+}
+
+/// Every part of a series, in reading order.
+#[derive(Debug, Serialize)]
+pub(super) struct Series {
+    series: String,
+    parts: Vec<Part>,
+}
+
+/// The parts of `series`, or `None` if no post belongs to it.
+///
+/// Parts are read in `series_order`, with any that don't give one after those that do. Ties are
+/// broken by date.
+pub(super) fn series(posts: &[Item], series: &str) -> Option<Series> {
+    let mut parts: Vec<&Item> = posts
+        .iter()
+        .filter(|post| post.series.as_deref() == Some(series))
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+    parts.sort_by_key(|post| {
+        (
+            post.series_order.is_none(),
+            post.series_order,
+            post.created_at,
+            &post.name,
+        )
+    });
+
+    let parts = parts
+        .into_iter()
+        .map(|post| Part {
+            name: post.name.clone(),
+            title: post.title.clone(),
+            description: post.description.clone(),
+            series_order: post.series_order,
+            created_at: post.created_at,
+        })
+        .collect();
+    Some(Series {
+        series: series.to_string(),
+        parts,
+    })
+}
+
+/// Every part of a series, in reading order.
+pub async fn handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let name = normalize_tag(&name).ok_or(ApiError::NotFound("Series"))?;
+    let posts = list_items(&state).await?;
+    let series = series(&posts, &name).ok_or(ApiError::NotFound("Series"))?;
+
+    let updated_at = posts
+        .iter()
+        .filter(|post| post.series.as_deref() == Some(name.as_str()))
+        .map(Item::changed_at)
+        .max();
+    respond_json(CachePolicy::Listing, &headers, &series, updated_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(name: &str, series: Option<&str>, series_order: Option<u32>, day: u16) -> Item {
+        let created_at = time::Date::from_ordinal_date(2024, day)
+            .unwrap()
+            .midnight()
+            .assume_utc();
+        Item {
+            name: name.to_string(),
+            title: name.to_string(),
+            description: String::new(),
+            tags: Vec::new(),
+            series: series.map(str::to_string),
+            series_order,
+            draft: false,
+            publish_at: None,
+            created_at,
+            updated_at: created_at,
+            stats: None,
+        }
+    }
+
+    #[test]
+    fn test_orders_series_parts() {
+        let posts = vec![
+            post("epilogue", Some("rust"), None, 1),
+            post("part-two", Some("rust"), Some(2), 2),
+            post("unrelated", None, None, 3),
+            post("part-one", Some("rust"), Some(1), 4),
+            post("appendix", Some("rust"), None, 5),
+        ];
+
+        let names: Vec<_> = series(&posts, "rust")
+            .unwrap()
+            .parts
+            .into_iter()
+            .map(|part| part.name)
+            .collect();
+        assert_eq!(names, ["part-one", "part-two", "epilogue", "appendix"]);
+        assert!(series(&posts, "go").is_none());
+    }
+}
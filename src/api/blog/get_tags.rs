@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use serde::Serialize;
+
+use super::get_items::list_items;
+use crate::api::conditional::{respond_json, CachePolicy};
+use crate::api::error::ApiError;
+use crate::app::AppState;
+
+#[derive(Debug, Serialize)]
+struct TagCount {
+    tag: String,
+    count: usize,
+}
+
+/// Every tag in use, with how many posts carry it, most used first.
+pub async fn handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let posts = list_items(&state).await?;
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for tag in posts.into_iter().flat_map(|post| post.tags) {
+        *counts.entry(tag).or_default() += 1;
+    }
+
+    let mut tags: Vec<TagCount> = counts
+        .into_iter()
+        .map(|(tag, count)| TagCount { tag, count })
+        .collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+
+    respond_json(CachePolicy::Listing, &headers, &tags, None)
+}
@@ -0,0 +1,34 @@
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+
+use super::get_content::post_html;
+use super::get_items::{visible_item, PreviewParams};
+use crate::api::conditional::{respond_json, CachePolicy};
+use crate::api::error::ApiError;
+use crate::app::AppState;
+use crate::content::outline;
+use crate::slug::Slug;
+
+/// The table of contents of a post: its `h2` and `h3` headings in order, with the ids its HTML
+/// links them by.
+pub async fn handler(
+    State(state): State<AppState>,
+    slug: Slug,
+    Query(params): Query<PreviewParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let name = slug.into_string();
+    let visible = visible_item(&state, &name, &params)
+        .await?
+        .ok_or(ApiError::NotFound("Writing"))?;
+    let html = post_html(&state, name).await?;
+
+    let headings = outline(&String::from_utf8_lossy(&html));
+    respond_json(
+        visible.cache_policy(CachePolicy::Post),
+        &headers,
+        &headings,
+        Some(visible.item.updated_at),
+    )
+}
@@ -7,7 +7,16 @@ use tower_http::cors::{Any, CorsLayer};
 use crate::app::AppState;
 
 mod get_content;
+mod get_item;
 mod get_items;
+mod get_nav;
+mod get_related;
+mod get_series;
+mod get_tags;
+mod get_toc;
+
+pub(super) use get_content::post_html;
+pub(super) use get_items::{list_items, Item};
 
 pub fn router(state: AppState) -> Router<AppState> {
     let cors_layer = CorsLayer::new()
@@ -18,8 +27,14 @@ pub fn router(state: AppState) -> Router<AppState> {
 
     Router::new()
         .route("/", get(get_items::handler))
+        .route("/tags", get(get_tags::handler))
+        .route("/series/:series", get(get_series::handler))
         .route("/:name", get(get_content::handler))
-        // TODO: get content
+        .route("/:name/", get(get_content::handler))
+        .route("/:name/item", get(get_item::handler))
+        .route("/:name/toc", get(get_toc::handler))
+        .route("/:name/related", get(get_related::handler))
+        .route("/:name/nav", get(get_nav::handler))
         .with_state(state)
         .layer(cors_layer)
 }
@@ -0,0 +1,216 @@
+use std::time::{Duration, SystemTime};
+
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum_extra::headers::{
+    CacheControl, ETag, HeaderMapExt, IfModifiedSince, IfNoneMatch, IfRange, LastModified,
+};
+use bytes::Bytes;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+use crate::api::error::ApiError;
+
+/// How long clients may reuse what each class of route serves before revalidating it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum CachePolicy {
+    /// Listings change whenever anything is published, so they are always revalidated.
+    Listing,
+    /// A post's HTML, reused for a few minutes.
+    Post,
+    /// Images rarely change once uploaded, reused for a day.
+    Image,
+    /// Stylesheets only change with a deploy, reused for an hour.
+    Stylesheet,
+    /// An unpublished post seen through a preview link, which no shared cache may keep.
+    Preview,
+}
+
+impl CachePolicy {
+    fn header(self) -> CacheControl {
+        let cache_control = CacheControl::new().with_public();
+        match self {
+            CachePolicy::Listing => cache_control.with_no_cache(),
+            CachePolicy::Post => cache_control.with_max_age(Duration::from_secs(300)),
+            CachePolicy::Image => cache_control.with_max_age(Duration::from_secs(86_400)),
+            CachePolicy::Stylesheet => cache_control.with_max_age(Duration::from_secs(3_600)),
+            CachePolicy::Preview => CacheControl::new().with_private().with_no_store(),
+        }
+    }
+}
+
+/// The validators a response is served with, and the means to check a request's preconditions
+/// against them.
+#[derive(Clone, Debug)]
+pub(crate) struct Validators {
+    etag: Option<ETag>,
+    last_modified: Option<LastModified>,
+    policy: CachePolicy,
+}
+
+impl Validators {
+    /// Validators for a body we hold in full, with a strong ETag hashed from its bytes.
+    pub(crate) fn new(
+        policy: CachePolicy,
+        bytes: &[u8],
+        updated_at: Option<OffsetDateTime>,
+    ) -> Self {
+        let etag = format!("\"{:x}\"", Sha256::digest(bytes)).parse().ok();
+
+        Self {
+            etag,
+            ..Self::without_etag(policy, updated_at)
+        }
+    }
+
+    /// Validators for a body we can't hash before sending, such as one streamed from upstream.
+    pub(crate) fn without_etag(policy: CachePolicy, updated_at: Option<OffsetDateTime>) -> Self {
+        Self {
+            etag: None,
+            last_modified: updated_at
+                .map(|updated_at| LastModified::from(SystemTime::from(updated_at))),
+            policy,
+        }
+    }
+
+    /// Whether the client already holds what we'd send, going by `If-None-Match`, or
+    /// `If-Modified-Since` when there is no `If-None-Match`.
+    pub(crate) fn is_fresh(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = headers.typed_get::<IfNoneMatch>() {
+            return self
+                .etag
+                .as_ref()
+                .is_some_and(|etag| !if_none_match.precondition_passes(etag));
+        }
+
+        match (headers.typed_get::<IfModifiedSince>(), self.last_modified) {
+            (Some(if_modified_since), Some(last_modified)) => {
+                !if_modified_since.is_modified(last_modified.into())
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a `Range` should be honoured, which it shouldn't be if an `If-Range` names a
+    /// representation other than the one we're about to send.
+    pub(crate) fn range_applies(&self, headers: &HeaderMap) -> bool {
+        headers.typed_get::<IfRange>().map_or(true, |if_range| {
+            !if_range.is_modified(self.etag.as_ref(), self.last_modified.as_ref())
+        })
+    }
+
+    /// A bodiless 304 for a client whose copy is still current.
+    pub(crate) fn not_modified(&self) -> Response {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        self.apply(response.headers_mut());
+        response
+    }
+
+    /// Set our validators and `Cache-Control` on a successful response.
+    pub(crate) fn apply(&self, headers: &mut HeaderMap) {
+        if let Some(etag) = &self.etag {
+            headers.typed_insert(etag.clone());
+        }
+        if let Some(last_modified) = self.last_modified {
+            headers.typed_insert(last_modified);
+        }
+        headers.typed_insert(self.policy.header());
+    }
+}
+
+/// Serve `body` as `content_type` under `policy`, or a 304 if the client already holds it.
+pub(crate) fn respond(
+    policy: CachePolicy,
+    headers: &HeaderMap,
+    content_type: &'static str,
+    body: impl Into<Bytes>,
+    updated_at: Option<OffsetDateTime>,
+) -> Response {
+    let body = body.into();
+    let validators = Validators::new(policy, &body, updated_at);
+    if validators.is_fresh(headers) {
+        return validators.not_modified();
+    }
+
+    let mut response = ([(header::CONTENT_TYPE, content_type)], body).into_response();
+    validators.apply(response.headers_mut());
+    response
+}
+
+/// Serve `value` encoded as JSON, like [`respond`].
+pub(crate) fn respond_json<T: Serialize>(
+    policy: CachePolicy,
+    headers: &HeaderMap,
+    value: &T,
+    updated_at: Option<OffsetDateTime>,
+) -> Result<Response, ApiError> {
+    let body = serde_json::to_vec(value)?;
+    Ok(respond(
+        policy,
+        headers,
+        "application/json",
+        body,
+        updated_at,
+    ))
+}
+
+/// The `updated_at` of the entry called `name` in a raw manifest listing, if there is one.
+pub(crate) fn updated_at(listing: &[Value], name: &str) -> Option<OffsetDateTime> {
+    let entry = listing
+        .iter()
+        .find(|entry| entry.get(0).and_then(Value::as_str) == Some(name))?;
+    let updated_at = entry.get(1)?.get(1)?.get("updated_at")?;
+
+    serde_json::from_value(updated_at.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_matches_etags_and_dates() {
+        let updated_at = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let validators = Validators::new(CachePolicy::Post, b"hello", Some(updated_at));
+        let mut response = HeaderMap::new();
+        validators.apply(&mut response);
+        let etag = response[header::ETAG].to_str().unwrap();
+        let last_modified = response[header::LAST_MODIFIED].to_str().unwrap();
+
+        assert!(validators.is_fresh(&request(header::IF_NONE_MATCH, etag)));
+        assert!(!validators.is_fresh(&request(header::IF_NONE_MATCH, "\"other\"")));
+        assert!(validators.is_fresh(&request(header::IF_MODIFIED_SINCE, last_modified)));
+        assert!(!validators.is_fresh(&request(
+            header::IF_MODIFIED_SINCE,
+            "Mon, 01 Jan 2001 00:00:00 GMT"
+        )));
+
+        assert!(validators.range_applies(&request(header::IF_RANGE, etag)));
+        assert!(!validators.range_applies(&request(header::IF_RANGE, "\"other\"")));
+        assert!(validators.range_applies(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_finds_updated_at_in_listing() {
+        let listing: Vec<Value> = serde_json::from_str(
+            r#"[["a", ["cid", {"created_at": [2024, 1, 0, 0, 0, 0, 0, 0, 0],
+                               "updated_at": [2024, 2, 0, 0, 0, 0, 0, 0, 0],
+                               "metadata": {}}]]]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            updated_at(&listing, "a").map(|updated_at| updated_at.ordinal()),
+            Some(2)
+        );
+        assert_eq!(updated_at(&listing, "b"), None);
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use axum::routing::get;
+use axum::{Extension, Router};
+
+use crate::app::AppState;
+
+mod robots;
+mod sitemap;
+
+/// The paths of `WebApp`'s routes that don't take parameters, which the sitemap lists alongside
+/// every post and image.
+#[derive(Clone, Debug)]
+struct StaticRoutes(Arc<[String]>);
+
+/// `/sitemap.xml` and `/robots.txt`, served from the root of the site. `routes` are the paths of
+/// every route in `WebApp`.
+pub fn router(state: AppState, routes: &[String]) -> Router<AppState> {
+    let mut static_routes: Vec<String> = routes
+        .iter()
+        .filter(|path| !path.contains([':', '*']))
+        .map(|path| match path.trim_end_matches('/') {
+            "" => "/".to_string(),
+            path => path.to_string(),
+        })
+        .collect();
+    static_routes.sort();
+    static_routes.dedup();
+
+    Router::new()
+        .route("/sitemap.xml", get(sitemap::handler))
+        .route("/robots.txt", get(robots::handler))
+        .layer(Extension(StaticRoutes(static_routes.into())))
+        .with_state(state)
+}
+
+/// Whether `path` falls under any of the `disallow` prefixes robots.txt hands out.
+fn is_disallowed(path: &str, disallow: &[String]) -> bool {
+    disallow
+        .iter()
+        .any(|prefix| path.starts_with(prefix.as_str()))
+}
@@ -0,0 +1,56 @@
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use url::Url;
+
+use crate::api::absolute_url;
+use crate::api::conditional::{respond, CachePolicy};
+use crate::app::AppState;
+
+pub async fn handler(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let body = robots(&state.site_url, &state.robots_disallow);
+    respond(
+        CachePolicy::Listing,
+        &headers,
+        "text/plain; charset=utf-8",
+        body,
+        None,
+    )
+}
+
+/// A robots.txt keeping every crawler out of `disallow` and pointing them at the sitemap.
+fn robots(site_url: &Url, disallow: &[String]) -> String {
+    let mut robots = String::from("User-agent: *\n");
+    if disallow.is_empty() {
+        // An empty Disallow allows everything, a group needs at least one rule.
+        robots.push_str("Disallow:\n");
+    }
+    for path in disallow {
+        robots.push_str(&format!("Disallow: {path}\n"));
+    }
+    robots.push_str(&format!(
+        "\nSitemap: {}\n",
+        absolute_url(site_url, "/sitemap.xml")
+    ));
+    robots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_robots_lists_rules_and_sitemap() {
+        let site_url = Url::parse("https://example.com").unwrap();
+
+        assert_eq!(
+            robots(&site_url, &["/_status/".to_string(), "/search".to_string()]),
+            "User-agent: *\nDisallow: /_status/\nDisallow: /search\n\n\
+             Sitemap: https://example.com/sitemap.xml\n"
+        );
+        assert_eq!(
+            robots(&site_url, &[]),
+            "User-agent: *\nDisallow:\n\nSitemap: https://example.com/sitemap.xml\n"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::Extension;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use url::Url;
+
+use super::{is_disallowed, StaticRoutes};
+use crate::api::conditional::{respond, CachePolicy};
+use crate::api::error::ApiError;
+use crate::api::{absolute_url, blog, escape_xml, gallery};
+use crate::app::AppState;
+use crate::slug::Slug;
+
+/// A page in the sitemap, and when it last changed if we know.
+#[derive(Debug)]
+struct SitemapUrl {
+    path: String,
+    lastmod: Option<OffsetDateTime>,
+}
+
+/// Every static page, post, tag and image that crawlers are allowed to visit.
+pub async fn handler(
+    State(state): State<AppState>,
+    Extension(StaticRoutes(static_routes)): Extension<StaticRoutes>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let posts = blog::list_items(&state).await?;
+    let images = gallery::list_items(&state).await?;
+    let posts_updated_at = posts.iter().map(blog::Item::changed_at).max();
+    let images_updated_at = images.iter().map(|image| image.updated_at).max();
+
+    let mut urls: Vec<SitemapUrl> = static_routes
+        .iter()
+        .map(|path| SitemapUrl {
+            lastmod: match path.as_str() {
+                "/blog" => posts_updated_at,
+                "/gallery" => images_updated_at,
+                _ => None,
+            },
+            path: path.clone(),
+        })
+        .collect();
+
+    // Names that aren't canonical would only be redirected, so only canonical ones are listed.
+    let mut tags: BTreeMap<&str, OffsetDateTime> = BTreeMap::new();
+    for post in posts.iter().filter(|post| Slug::is_canonical(&post.name)) {
+        urls.push(SitemapUrl {
+            path: format!("/blog/{}", post.name),
+            lastmod: Some(post.changed_at()),
+        });
+        for tag in post.tags.iter().filter(|tag| Slug::is_canonical(tag)) {
+            let updated_at = tags.entry(tag).or_insert(post.changed_at());
+            *updated_at = (*updated_at).max(post.changed_at());
+        }
+    }
+    urls.extend(tags.into_iter().map(|(tag, updated_at)| SitemapUrl {
+        path: format!("/blog/tag/{tag}"),
+        lastmod: Some(updated_at),
+    }));
+    urls.extend(
+        images
+            .iter()
+            .filter(|image| Slug::is_canonical(&image.name))
+            .map(|image| SitemapUrl {
+                path: format!("/gallery/{}", image.name),
+                lastmod: Some(image.updated_at),
+            }),
+    );
+    urls.retain(|url| !is_disallowed(&url.path, &state.robots_disallow));
+
+    let body = render(&state.site_url, &urls)?;
+    Ok(respond(
+        CachePolicy::Listing,
+        &headers,
+        "application/xml; charset=utf-8",
+        body,
+        posts_updated_at.max(images_updated_at),
+    ))
+}
+
+fn render(site_url: &Url, urls: &[SitemapUrl]) -> Result<String, time::error::Format> {
+    let mut xml = String::from(concat!(
+        r#"<?xml version="1.0" encoding="utf-8"?>"#,
+        r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+    ));
+    for url in urls {
+        xml.push_str("<url><loc>");
+        xml.push_str(&escape_xml(&absolute_url(site_url, &url.path)));
+        xml.push_str("</loc>");
+        if let Some(lastmod) = url.lastmod {
+            // Crawlers don't need anything finer than a second.
+            let lastmod = lastmod - time::Duration::nanoseconds(lastmod.nanosecond().into());
+            xml.push_str(&format!("<lastmod>{}</lastmod>", lastmod.format(&Rfc3339)?));
+        }
+        xml.push_str("</url>");
+    }
+    xml.push_str("</urlset>");
+
+    Ok(xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_sitemap() {
+        let site_url = Url::parse("https://example.com/").unwrap();
+        let urls = [
+            SitemapUrl {
+                path: "/".to_string(),
+                lastmod: None,
+            },
+            SitemapUrl {
+                path: "/blog/a?b&c".to_string(),
+                lastmod: Some(
+                    OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_500_000_000).unwrap(),
+                ),
+            },
+        ];
+
+        assert_eq!(
+            render(&site_url, &urls).unwrap(),
+            concat!(
+                r#"<?xml version="1.0" encoding="utf-8"?>"#,
+                r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+                "<url><loc>https://example.com/</loc></url>",
+                "<url><loc>https://example.com/blog/a?b&amp;c</loc>",
+                "<lastmod>2023-11-14T22:13:20Z</lastmod></url>",
+                "</urlset>",
+            )
+        );
+    }
+}
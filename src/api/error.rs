@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use axum::extract::Json;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+use crate::api::pagination::PaginationError;
+use crate::api::retry_after_header;
+use crate::content::ContentSourceError;
+use crate::leaky::LeakyClientError;
+
+/// Anything an API route can fail with. Every failure is answered with a JSON `{"msg": ...}` body,
+/// and server-side ones are logged.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("{0} not found")]
+    NotFound(&'static str),
+    #[error("bad request: {0}")]
+    BadRequest(String),
+    #[error("leaky is unreachable: {0}")]
+    UpstreamUnreachable(LeakyClientError),
+    #[error("leaky timed out: {0}")]
+    UpstreamTimeout(LeakyClientError),
+    #[error("leaky responded with {0}")]
+    BadStatus(StatusCode),
+    #[error("leaky response could not be decoded: {0}")]
+    UndecodableBody(LeakyClientError),
+    #[error("leaky is unavailable, retry after {0:?}")]
+    UpstreamUnavailable(Duration),
+    #[error("content source failed: {0}")]
+    SourceFailed(ContentSourceError),
+    #[error("malformed entry {name:?}: {reason}")]
+    MalformedEntry { name: String, reason: String },
+    #[error("failed to encode response: {0}")]
+    EncodeFailed(#[from] serde_json::Error),
+    #[error("failed to format a date: {0}")]
+    UnformattableDate(#[from] time::error::Format),
+    #[error("failed to build response: {0}")]
+    ResponseBuildFailed(#[from] axum::http::Error),
+}
+
+impl From<ContentSourceError> for ApiError {
+    fn from(err: ContentSourceError) -> Self {
+        match err {
+            ContentSourceError::Leaky(err) => err.into(),
+            ContentSourceError::NotFound => ApiError::NotFound("Content"),
+            err => ApiError::SourceFailed(err),
+        }
+    }
+}
+
+impl From<LeakyClientError> for ApiError {
+    fn from(err: LeakyClientError) -> Self {
+        match err {
+            LeakyClientError::TimedOut(_) => ApiError::UpstreamTimeout(err),
+            LeakyClientError::NotFound => ApiError::NotFound("Content"),
+            LeakyClientError::BadStatus(status) => ApiError::BadStatus(status),
+            LeakyClientError::ResponseReadFailed(_) | LeakyClientError::UndecodableBody(_) => {
+                ApiError::UndecodableBody(err)
+            }
+            LeakyClientError::UrlJoinFailed(_) | LeakyClientError::Unreachable(_) => {
+                ApiError::UpstreamUnreachable(err)
+            }
+            LeakyClientError::CircuitOpen { retry_after } => {
+                ApiError::UpstreamUnavailable(retry_after)
+            }
+        }
+    }
+}
+
+impl From<PaginationError> for ApiError {
+    fn from(err: PaginationError) -> Self {
+        ApiError::BadRequest(err.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = match &self {
+            ApiError::NotFound(what) => (StatusCode::NOT_FOUND, format!("{what} not found")),
+            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            ApiError::UpstreamUnavailable(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Upstream unavailable".to_string(),
+            ),
+            ApiError::UpstreamTimeout(_) => (
+                StatusCode::GATEWAY_TIMEOUT,
+                "Timed out fetching content".to_string(),
+            ),
+            ApiError::UpstreamUnreachable(_) => (
+                StatusCode::BAD_GATEWAY,
+                "Failed to fetch content".to_string(),
+            ),
+            ApiError::BadStatus(_)
+            | ApiError::UndecodableBody(_)
+            | ApiError::MalformedEntry { .. } => (
+                StatusCode::BAD_GATEWAY,
+                "Invalid response fetching content".to_string(),
+            ),
+            ApiError::SourceFailed(_)
+            | ApiError::EncodeFailed(_)
+            | ApiError::UnformattableDate(_)
+            | ApiError::ResponseBuildFailed(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            ),
+        };
+        if status.is_server_error() {
+            tracing::error!("failed to serve request: {self}");
+        }
+
+        let err_msg = serde_json::json!({"msg": error_message});
+        match self {
+            ApiError::UpstreamUnavailable(retry_after) => {
+                (status, [retry_after_header(retry_after)], Json(err_msg)).into_response()
+            }
+            _ => (status, Json(err_msg)).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::header;
+
+    use super::*;
+
+    #[test]
+    fn test_maps_errors_to_statuses() {
+        let status = |err: ApiError| err.into_response().status();
+
+        assert_eq!(status(ApiError::NotFound("Writing")), StatusCode::NOT_FOUND);
+        assert_eq!(
+            status(ContentSourceError::NotFound.into()),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(LeakyClientError::BadStatus(StatusCode::IM_A_TEAPOT).into()),
+            StatusCode::BAD_GATEWAY
+        );
+        assert_eq!(
+            status(ContentSourceError::Unavailable.into()),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        let unavailable = ApiError::from(LeakyClientError::CircuitOpen {
+            retry_after: Duration::from_millis(1500),
+        })
+        .into_response();
+        assert_eq!(unavailable.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(unavailable.headers()[header::RETRY_AFTER], "2");
+    }
+}
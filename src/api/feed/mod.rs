@@ -0,0 +1,29 @@
+use axum::routing::get;
+use axum::Router;
+
+use crate::app::AppState;
+
+mod render;
+mod visual;
+mod writing;
+
+/// The name feeds go by, matching the site's title.
+const SITE_TITLE: &str = "Krondor";
+
+/// How many of the newest posts or images a feed holds.
+const FEED_LEN: usize = 20;
+
+const RSS_CONTENT_TYPE: &str = "application/rss+xml; charset=utf-8";
+const ATOM_CONTENT_TYPE: &str = "application/atom+xml; charset=utf-8";
+const JSON_FEED_CONTENT_TYPE: &str = "application/feed+json; charset=utf-8";
+
+/// Feeds are served from the root of the site rather than under the API, where feed readers
+/// expect to find them.
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/feed.xml", get(writing::rss))
+        .route("/atom.xml", get(writing::atom))
+        .route("/feed.json", get(writing::json))
+        .route("/gallery/feed.xml", get(visual::rss))
+        .with_state(state)
+}
@@ -0,0 +1,232 @@
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::OffsetDateTime;
+
+use crate::api::escape_xml;
+
+/// What a feed is and where it lives.
+#[derive(Debug)]
+pub(super) struct Channel {
+    pub(super) title: String,
+    pub(super) description: String,
+    /// The page the feed mirrors.
+    pub(super) home_page_url: String,
+    /// Where the feed itself is served.
+    pub(super) feed_url: String,
+    pub(super) updated_at: Option<OffsetDateTime>,
+}
+
+/// One post or image in a feed.
+#[derive(Debug)]
+pub(super) struct Entry {
+    pub(super) url: String,
+    pub(super) title: String,
+    pub(super) summary: String,
+    pub(super) content_html: Option<String>,
+    pub(super) tags: Vec<String>,
+    pub(super) enclosure: Option<Enclosure>,
+    pub(super) published_at: OffsetDateTime,
+    pub(super) updated_at: OffsetDateTime,
+}
+
+/// A file attached to an entry.
+#[derive(Debug)]
+pub(super) struct Enclosure {
+    pub(super) url: String,
+    pub(super) content_type: String,
+}
+
+pub(super) fn rss(channel: &Channel, entries: &[Entry]) -> Result<String, time::error::Format> {
+    let mut xml = String::from(concat!(
+        r#"<?xml version="1.0" encoding="utf-8"?>"#,
+        r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" "#,
+        r#"xmlns:content="http://purl.org/rss/1.0/modules/content/"><channel>"#,
+    ));
+    element(&mut xml, "title", &channel.title);
+    element(&mut xml, "link", &channel.home_page_url);
+    element(&mut xml, "description", &channel.description);
+    xml.push_str(&format!(
+        r#"<atom:link href="{}" rel="self" type="application/rss+xml"/>"#,
+        escape_xml(&channel.feed_url)
+    ));
+    if let Some(updated_at) = channel.updated_at {
+        element(&mut xml, "lastBuildDate", &updated_at.format(&Rfc2822)?);
+    }
+
+    for entry in entries {
+        xml.push_str("<item>");
+        element(&mut xml, "title", &entry.title);
+        element(&mut xml, "link", &entry.url);
+        xml.push_str(&format!(
+            r#"<guid isPermaLink="true">{}</guid>"#,
+            escape_xml(&entry.url)
+        ));
+        element(&mut xml, "description", &entry.summary);
+        if let Some(content_html) = &entry.content_html {
+            element(&mut xml, "content:encoded", content_html);
+        }
+        for tag in &entry.tags {
+            element(&mut xml, "category", tag);
+        }
+        if let Some(enclosure) = &entry.enclosure {
+            // RSS wants a length, but the listing doesn't record image sizes. Zero is the
+            // conventional stand-in for unknown.
+            xml.push_str(&format!(
+                r#"<enclosure url="{}" type="{}" length="0"/>"#,
+                escape_xml(&enclosure.url),
+                escape_xml(&enclosure.content_type)
+            ));
+        }
+        element(&mut xml, "pubDate", &entry.published_at.format(&Rfc2822)?);
+        xml.push_str("</item>");
+    }
+
+    xml.push_str("</channel></rss>");
+    Ok(xml)
+}
+
+pub(super) fn atom(channel: &Channel, entries: &[Entry]) -> Result<String, time::error::Format> {
+    let mut xml = String::from(concat!(
+        r#"<?xml version="1.0" encoding="utf-8"?>"#,
+        r#"<feed xmlns="http://www.w3.org/2005/Atom">"#,
+    ));
+    element(&mut xml, "title", &channel.title);
+    element(&mut xml, "subtitle", &channel.description);
+    element(&mut xml, "id", &channel.feed_url);
+    xml.push_str(&format!(
+        r#"<link rel="self" type="application/atom+xml" href="{}"/>"#,
+        escape_xml(&channel.feed_url)
+    ));
+    xml.push_str(&format!(
+        r#"<link rel="alternate" type="text/html" href="{}"/>"#,
+        escape_xml(&channel.home_page_url)
+    ));
+    // Atom requires an updated date even on an empty feed.
+    let updated_at = channel.updated_at.unwrap_or(OffsetDateTime::UNIX_EPOCH);
+    element(&mut xml, "updated", &updated_at.format(&Rfc3339)?);
+    xml.push_str("<author>");
+    element(&mut xml, "name", &channel.title);
+    xml.push_str("</author>");
+
+    for entry in entries {
+        xml.push_str("<entry>");
+        element(&mut xml, "title", &entry.title);
+        element(&mut xml, "id", &entry.url);
+        xml.push_str(&format!(
+            r#"<link rel="alternate" type="text/html" href="{}"/>"#,
+            escape_xml(&entry.url)
+        ));
+        if let Some(enclosure) = &entry.enclosure {
+            xml.push_str(&format!(
+                r#"<link rel="enclosure" type="{}" href="{}"/>"#,
+                escape_xml(&enclosure.content_type),
+                escape_xml(&enclosure.url)
+            ));
+        }
+        element(&mut xml, "published", &entry.published_at.format(&Rfc3339)?);
+        element(&mut xml, "updated", &entry.updated_at.format(&Rfc3339)?);
+        element(&mut xml, "summary", &entry.summary);
+        if let Some(content_html) = &entry.content_html {
+            xml.push_str(r#"<content type="html">"#);
+            xml.push_str(&escape_xml(content_html));
+            xml.push_str("</content>");
+        }
+        for tag in &entry.tags {
+            xml.push_str(&format!(r#"<category term="{}"/>"#, escape_xml(tag)));
+        }
+        xml.push_str("</entry>");
+    }
+
+    xml.push_str("</feed>");
+    Ok(xml)
+}
+
+/// A JSON Feed 1.1 document.
+pub(super) fn json(
+    channel: &Channel,
+    entries: &[Entry],
+) -> Result<serde_json::Value, time::error::Format> {
+    let items = entries
+        .iter()
+        .map(|entry| {
+            let mut item = serde_json::json!({
+                "id": entry.url,
+                "url": entry.url,
+                "title": entry.title,
+                "summary": entry.summary,
+                "date_published": entry.published_at.format(&Rfc3339)?,
+                "date_modified": entry.updated_at.format(&Rfc3339)?,
+                "tags": entry.tags,
+            });
+            // JSON Feed requires one of content_html or content_text.
+            match &entry.content_html {
+                Some(content_html) => item["content_html"] = content_html.as_str().into(),
+                None => item["content_text"] = entry.summary.as_str().into(),
+            }
+            if let Some(enclosure) = &entry.enclosure {
+                item["attachments"] = serde_json::json!([{
+                    "url": enclosure.url,
+                    "mime_type": enclosure.content_type,
+                }]);
+            }
+            Ok(item)
+        })
+        .collect::<Result<Vec<_>, time::error::Format>>()?;
+
+    Ok(serde_json::json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": channel.title,
+        "description": channel.description,
+        "home_page_url": channel.home_page_url,
+        "feed_url": channel.feed_url,
+        "items": items,
+    }))
+}
+
+fn element(xml: &mut String, name: &str, text: &str) {
+    xml.push_str(&format!("<{name}>{}</{name}>", escape_xml(text)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel() -> Channel {
+        Channel {
+            title: "Krondor".to_string(),
+            description: "Writing".to_string(),
+            home_page_url: "https://example.com/blog".to_string(),
+            feed_url: "https://example.com/feed.xml".to_string(),
+            updated_at: Some(OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap()),
+        }
+    }
+
+    fn entries() -> Vec<Entry> {
+        vec![Entry {
+            url: "https://example.com/blog/a".to_string(),
+            title: "Rock & Roll".to_string(),
+            summary: "<loud>".to_string(),
+            content_html: Some("<p>Hi</p>".to_string()),
+            tags: vec!["music".to_string()],
+            enclosure: None,
+            published_at: OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap(),
+            updated_at: OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap(),
+        }]
+    }
+
+    #[test]
+    fn test_renders_escaped_feeds() {
+        let rss = rss(&channel(), &entries()).unwrap();
+        assert!(rss.contains("<title>Rock &amp; Roll</title>"));
+        assert!(rss.contains("<description>&lt;loud&gt;</description>"));
+        assert!(rss.contains("<content:encoded>&lt;p&gt;Hi&lt;/p&gt;</content:encoded>"));
+        assert!(rss.contains("<pubDate>Sun, 13 Sep 2020 12:26:40 +0000</pubDate>"));
+
+        let atom = atom(&channel(), &entries()).unwrap();
+        assert!(atom.contains("<updated>2023-11-14T22:13:20Z</updated>"));
+        assert!(atom.contains(r#"<category term="music"/>"#));
+
+        let json = json(&channel(), &entries()).unwrap();
+        assert_eq!(json["items"][0]["title"], "Rock & Roll");
+        assert_eq!(json["items"][0]["content_html"], "<p>Hi</p>");
+    }
+}
@@ -0,0 +1,62 @@
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::Response;
+
+use super::render::{self, Channel, Enclosure, Entry};
+use super::{FEED_LEN, RSS_CONTENT_TYPE, SITE_TITLE};
+use crate::api::absolute_url;
+use crate::api::conditional::{respond, CachePolicy};
+use crate::api::error::ApiError;
+use crate::api::gallery::{list_items, resolve_content_type};
+use crate::app::AppState;
+use crate::slug::Slug;
+
+/// The newest images, each attached as an enclosure.
+pub async fn rss(State(state): State<AppState>, headers: HeaderMap) -> Result<Response, ApiError> {
+    let mut images = list_items(&state).await?;
+    let updated_at = images.iter().map(|image| image.updated_at).max();
+    images.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    images.truncate(FEED_LEN);
+
+    let entries: Vec<Entry> = images
+        .into_iter()
+        // Names that aren't canonical slugs have no page to link to, and aren't safe to put in
+        // the markup below.
+        .filter(|image| Slug::is_canonical(&image.name))
+        .map(|image| {
+            let src = absolute_url(&state.site_url, &format!("/api/v0/gallery/{}", image.name));
+
+            Entry {
+                url: absolute_url(&state.site_url, &format!("/gallery/{}", image.name)),
+                summary: format!(r#"<img src="{}" alt="{}"/>"#, src, image.name),
+                content_html: None,
+                tags: Vec::new(),
+                enclosure: Some(Enclosure {
+                    url: src,
+                    // Without fetching the image, its name is all there is to go on.
+                    content_type: resolve_content_type(None, &[], &image.name),
+                }),
+                published_at: image.created_at,
+                updated_at: image.updated_at,
+                title: image.name,
+            }
+        })
+        .collect();
+
+    let channel = Channel {
+        title: format!("{SITE_TITLE} gallery"),
+        description: format!("Images from {SITE_TITLE}"),
+        home_page_url: absolute_url(&state.site_url, "/gallery"),
+        feed_url: absolute_url(&state.site_url, "/gallery/feed.xml"),
+        updated_at,
+    };
+
+    let body = render::rss(&channel, &entries)?;
+    Ok(respond(
+        CachePolicy::Listing,
+        &headers,
+        RSS_CONTENT_TYPE,
+        body,
+        updated_at,
+    ))
+}
@@ -0,0 +1,98 @@
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use futures::stream::{self, StreamExt};
+
+use super::render::{self, Channel, Entry};
+use super::{ATOM_CONTENT_TYPE, FEED_LEN, JSON_FEED_CONTENT_TYPE, RSS_CONTENT_TYPE, SITE_TITLE};
+use crate::api::absolute_url;
+use crate::api::blog::{list_items, post_html, Item};
+use crate::api::conditional::{respond, CachePolicy};
+use crate::api::error::ApiError;
+use crate::app::AppState;
+
+/// How many post bodies are fetched at once while building a feed.
+const FETCH_CONCURRENCY: usize = 4;
+
+pub async fn rss(State(state): State<AppState>, headers: HeaderMap) -> Result<Response, ApiError> {
+    let (channel, entries) = feed(&state, "/feed.xml").await?;
+    let body = render::rss(&channel, &entries)?;
+    Ok(respond(
+        CachePolicy::Listing,
+        &headers,
+        RSS_CONTENT_TYPE,
+        body,
+        channel.updated_at,
+    ))
+}
+
+pub async fn atom(State(state): State<AppState>, headers: HeaderMap) -> Result<Response, ApiError> {
+    let (channel, entries) = feed(&state, "/atom.xml").await?;
+    let body = render::atom(&channel, &entries)?;
+    Ok(respond(
+        CachePolicy::Listing,
+        &headers,
+        ATOM_CONTENT_TYPE,
+        body,
+        channel.updated_at,
+    ))
+}
+
+pub async fn json(State(state): State<AppState>, headers: HeaderMap) -> Result<Response, ApiError> {
+    let (channel, entries) = feed(&state, "/feed.json").await?;
+    let body = serde_json::to_vec(&render::json(&channel, &entries)?)?;
+    Ok(respond(
+        CachePolicy::Listing,
+        &headers,
+        JSON_FEED_CONTENT_TYPE,
+        body,
+        channel.updated_at,
+    ))
+}
+
+/// The newest posts, with their full HTML. A post whose HTML can't be fetched is still listed,
+/// with only its description.
+async fn feed(state: &AppState, feed_path: &str) -> Result<(Channel, Vec<Entry>), ApiError> {
+    let mut posts = list_items(state).await?;
+    let updated_at = posts.iter().map(Item::changed_at).max();
+    posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    posts.truncate(FEED_LEN);
+
+    let entries = stream::iter(posts)
+        .map(|post| async move {
+            let content_html = match post_html(state, post.name.clone()).await {
+                Ok(html) => Some(String::from_utf8_lossy(&html).into_owned()),
+                Err(err) => {
+                    tracing::warn!(
+                        "leaving the content of {} out of the feed: {err}",
+                        post.name
+                    );
+                    None
+                }
+            };
+
+            Entry {
+                url: absolute_url(&state.site_url, &format!("/blog/{}", post.name)),
+                title: post.title,
+                summary: post.description,
+                content_html,
+                tags: post.tags,
+                enclosure: None,
+                published_at: post.created_at,
+                updated_at: post.updated_at,
+            }
+        })
+        .buffered(FETCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    let channel = Channel {
+        title: SITE_TITLE.to_string(),
+        description: format!("Writing from {SITE_TITLE}"),
+        home_page_url: absolute_url(&state.site_url, "/blog"),
+        feed_url: absolute_url(&state.site_url, feed_path),
+        updated_at,
+    };
+
+    Ok((channel, entries))
+}
@@ -0,0 +1,91 @@
+/// The content type images are served with when nothing better can be worked out.
+const FALLBACK_CONTENT_TYPE: &str = "application/octet-stream";
+
+pub const SVG_CONTENT_TYPE: &str = "image/svg+xml";
+
+/// How far into a text file we look for an `<svg` root element.
+const SVG_SNIFF_LEN: usize = 1_024;
+
+/// Work out the content type to serve an image with. An `image/*` type reported by the upstream is
+/// trusted as-is; anything else (including a missing header) is replaced by sniffing the bytes,
+/// and failing that, guessing from the name. Non-image types are never passed through, so a
+/// misbehaving upstream can't get HTML served from our origin.
+pub fn resolve(upstream: Option<&str>, bytes: &[u8], name: &str) -> String {
+    if let Some(content_type) = upstream {
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        if essence.to_ascii_lowercase().starts_with("image/") {
+            return essence.to_ascii_lowercase();
+        }
+    }
+
+    if let Some(content_type) = sniff(bytes) {
+        return content_type.to_string();
+    }
+
+    match mime_guess::from_path(name).first() {
+        Some(guess) if guess.type_() == mime_guess::mime::IMAGE => guess.essence_str().to_string(),
+        _ => FALLBACK_CONTENT_TYPE.to_string(),
+    }
+}
+
+/// Identify an image format from its magic bytes.
+fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+    ];
+
+    if let Some((_, content_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+    {
+        return Some(content_type);
+    }
+
+    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+
+    // ISO-BMFF: a box size, then `ftyp` and the major brand
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" && matches!(&bytes[8..12], b"avif" | b"avis") {
+        return Some("image/avif");
+    }
+
+    let head = &bytes[..bytes.len().min(SVG_SNIFF_LEN)];
+    let head = String::from_utf8_lossy(head);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    if (head.starts_with("<svg") || head.starts_with("<?xml") || head.starts_with("<!--"))
+        && head.contains("<svg")
+    {
+        return Some(SVG_CONTENT_TYPE);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_content_type() {
+        let png = b"\x89PNG\r\n\x1a\n0000";
+
+        assert_eq!(resolve(Some("image/gif"), png, "a.png"), "image/gif");
+        assert_eq!(resolve(None, png, "a.jpg"), "image/png");
+        assert_eq!(resolve(Some("text/html"), png, "a.jpg"), "image/png");
+        assert_eq!(
+            resolve(Some("application/octet-stream"), b"RIFF0000WEBPVP8 ", "a"),
+            "image/webp"
+        );
+        assert_eq!(resolve(None, b"\0\0\0\x1cftypavif", "a"), "image/avif");
+        assert_eq!(
+            resolve(None, b"<?xml version=\"1.0\"?>\n<svg xmlns=\"\"/>", "a"),
+            SVG_CONTENT_TYPE
+        );
+        assert_eq!(resolve(None, b"????", "a.jpeg"), "image/jpeg");
+        assert_eq!(resolve(None, b"????", "a.html"), FALLBACK_CONTENT_TYPE);
+    }
+}
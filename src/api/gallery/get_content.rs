@@ -1,74 +1,130 @@
-use crate::app::AppState;
 use axum::body::Body;
-use axum::extract::{Path, State};
-use axum::http::{header, StatusCode};
-use axum::response::{IntoResponse, Response};
-use reqwest::Client;
+use axum::extract::State;
+use axum::http::{header, response, HeaderMap, StatusCode};
+use axum::response::Response;
+use futures::StreamExt;
+use time::OffsetDateTime;
+
+use super::content_type;
+use crate::api::conditional::{self, CachePolicy, Validators};
+use crate::api::error::ApiError;
+use crate::api::range::{ranged_body, requested_range};
+use crate::app::AppState;
+use crate::cache::ResourceKind;
+use crate::slug::Slug;
+
+const SVG_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; style-src 'unsafe-inline'; img-src data:; sandbox";
 
 pub async fn handler(
     State(state): State<AppState>,
-    Path(name): Path<String>,
-) -> Result<impl IntoResponse, GetItemsError> {
-    let leaky_url = state.leaky_url.clone();
-    let client = Client::new();
-    let url = leaky_url
-        .join(&format!("/visual/{}", name))
-        .map_err(|_| GetItemsError::UrlJoinError)?;
-
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .map_err(|_| GetItemsError::RequestFailed)?;
-
-    if response.status() == StatusCode::NOT_FOUND {
-        return Err(GetItemsError::WritingNotFound);
+    slug: Slug,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let name = slug.into_string();
+    let updated_at = image_updated_at(&state, &name).await;
+    let key = format!("/visual/{}", name);
+    let content_source = state.content_source.clone();
+    let image_name = name.clone();
+    let cached = state
+        .content_cache
+        .cached_asset(ResourceKind::Image, &key, || async move {
+            content_source.get_image(&image_name).await
+        });
+
+    // Images we already hold are answered from memory, slicing out any requested range.
+    if let Some(asset) = cached {
+        let validators = Validators::new(CachePolicy::Image, &asset.bytes, updated_at);
+        if validators.is_fresh(&headers) {
+            return Ok(validators.not_modified());
+        }
+
+        let content_type =
+            content_type::resolve(asset.content_type.as_deref(), &asset.bytes, &name);
+        let range = requested_range(&headers, &validators);
+        let mut response = ranged_body(image_response(&content_type), range, asset.bytes)?;
+        validators.apply(response.headers_mut());
+        return Ok(response);
+    }
+
+    // We can't hash a body before streaming it, so only the date is available to validate against.
+    let validators = Validators::without_etag(CachePolicy::Image, updated_at);
+    if validators.is_fresh(&headers) {
+        return Ok(validators.not_modified());
     }
+    let range = requested_range(&headers, &validators);
 
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|_| GetItemsError::ResponseReadError)?;
+    // Otherwise stream the image through as it arrives. A range is left to the source to honour,
+    // and a whole image is kept in the cache once it has been sent.
+    let mut stream = state.content_source.stream_image(&name, range).await?;
 
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "image/jpeg")
-        .body(Body::from(bytes))
-        .map_err(|_| GetItemsError::ResponseBuildError)?)
+    let mut response = match &stream.content_range {
+        Some(content_range) => {
+            let content_type = content_type::resolve(stream.content_type.as_deref(), &[], &name);
+            image_response(&content_type)
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_RANGE, content_range)
+        }
+        None => {
+            // Sniff the content type from the first chunk, then put it back in front of the rest.
+            let first = stream.body.next().await.transpose()?.unwrap_or_default();
+            let content_type = content_type::resolve(stream.content_type.as_deref(), &first, &name);
+            let rest = std::mem::replace(&mut stream.body, futures::stream::empty().boxed());
+            stream.body = state.content_cache.tee_asset(
+                ResourceKind::Image,
+                &key,
+                stream.content_type.clone(),
+                stream.content_length,
+                futures::stream::once(async move { Ok(first) }).chain(rest),
+            );
+
+            image_response(&content_type).status(StatusCode::OK)
+        }
+    };
+
+    if let Some(content_length) = stream.content_length {
+        response = response.header(header::CONTENT_LENGTH, content_length);
+    }
+
+    let mut response = response
+        .header(header::ACCEPT_RANGES, "bytes")
+        .body(Body::from_stream(stream.body))?;
+    validators.apply(response.headers_mut());
+    Ok(response)
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum GetItemsError {
-    #[error("Failed to construct URL")]
-    UrlJoinError,
-    #[error("Failed to send request")]
-    RequestFailed,
-    #[error("Writing not found")]
-    WritingNotFound,
-    #[error("Failed to read response body")]
-    ResponseReadError,
-    #[error("Failed to build response")]
-    ResponseBuildError,
+/// When the image was last updated, going by the gallery listing. Validators are a nicety, so a
+/// listing we can't fetch just means the image is served without a `Last-Modified`.
+async fn image_updated_at(state: &AppState, name: &str) -> Option<OffsetDateTime> {
+    let content_source = state.content_source.clone();
+    let listing = state
+        .content_cache
+        .listing(
+            "/visual",
+            || async move { content_source.list_images().await },
+        )
+        .await;
+
+    match listing {
+        Ok(listing) => conditional::updated_at(&listing, name),
+        Err(err) => {
+            tracing::warn!("failed to fetch gallery listing for {name}: {err}");
+            None
+        }
+    }
 }
 
-impl IntoResponse for GetItemsError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            GetItemsError::UrlJoinError => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
-            }
-            GetItemsError::RequestFailed => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch writing")
-            }
-            GetItemsError::WritingNotFound => (StatusCode::NOT_FOUND, "Writing not found"),
-            GetItemsError::ResponseReadError => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read response")
-            }
-            GetItemsError::ResponseBuildError => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to build response",
-            ),
-        };
-        (status, error_message).into_response()
+/// Start a response for an image, with the headers that keep browsers from treating it as
+/// anything but the content type we resolved.
+fn image_response(content_type: &str) -> response::Builder {
+    let mut response = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+
+    // SVGs are documents that can carry script, never let one run in our origin.
+    if content_type == content_type::SVG_CONTENT_TYPE {
+        response = response.header(header::CONTENT_SECURITY_POLICY, SVG_CONTENT_SECURITY_POLICY);
     }
+
+    response
 }
@@ -1,73 +1,106 @@
-use axum::extract::{Json, State};
-use axum::http::StatusCode;
-use axum::response::{IntoResponse, Response};
-use reqwest::Client;
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use time::OffsetDateTime;
 
+use crate::api::conditional::{respond_json, CachePolicy};
+use crate::api::error::ApiError;
+use crate::api::pagination::{paginate, ListParams, Listed};
 use crate::app::AppState;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct ItemMetadata {}
 
+/// Timestamps arrive in `time`'s compact serde form:
+/// `[year, ordinal, hour, minute, second, nanosecond, offset_hours, offset_minutes, offset_seconds]`.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct ItemData {
-    created_at: [i64; 9],
-    updated_at: [i64; 9],
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
     metadata: ItemMetadata,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct Item {
-    name: String,
-    created_at: OffsetDateTime,
+pub(crate) struct Item {
+    pub(crate) name: String,
+    pub(crate) created_at: OffsetDateTime,
+    pub(crate) updated_at: OffsetDateTime,
 }
 
-pub async fn handler(State(state): State<AppState>) -> Result<impl IntoResponse, GetItemsError> {
-    let leaky_url = state.leaky_url.clone();
-    let client = Client::new();
-    let response: Vec<Value> = client
-        .get(leaky_url.join("/visual").unwrap())
-        .send()
-        .await
-        .unwrap()
-        .json()
-        .await
-        .unwrap();
-
-    let mut posts: Vec<Item> = response.iter().filter_map(parse_item_data).collect();
-
-    posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-    Ok((StatusCode::OK, Json(posts)))
-}
+impl Listed for Item {
+    fn name(&self) -> &str {
+        &self.name
+    }
 
-#[derive(Debug, thiserror::Error)]
-pub enum GetItemsError {}
+    fn created_at(&self) -> OffsetDateTime {
+        self.created_at
+    }
 
-impl IntoResponse for GetItemsError {
-    fn into_response(self) -> Response {
-        match self {}
+    fn updated_at(&self) -> OffsetDateTime {
+        self.updated_at
     }
 }
 
-use time::Date;
+pub async fn handler(
+    State(state): State<AppState>,
+    Query(params): Query<ListParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let posts = list_items(&state).await?;
 
-fn parse_item_data(value: &Value) -> Option<Item> {
-    let v_array = value.as_array().unwrap();
-    let v_name: String = v_array.get(0).unwrap().as_str().unwrap().to_string();
-    let v_values = v_array.get(1);
-    v_values
-        .and_then(|values| values.get(1))
-        .and_then(|data| serde_json::from_value::<ItemData>(data.clone()).ok())
-        .map(|data| {
-            let year = data.created_at[0] as i32;
-            let day_of_year = data.created_at[1] as u16;
-            let date = Date::from_ordinal_date(year, day_of_year).unwrap();
-
-            Item {
-                name: v_name,
-                created_at: date.with_hms(0, 0, 0).unwrap().assume_utc(),
+    let updated_at = posts.iter().map(|post| post.updated_at).max();
+    let page = paginate(posts, &params)?;
+
+    respond_json(CachePolicy::Listing, &headers, &page, updated_at)
+}
+
+/// Every well-formed image in the visual listing, in listing order.
+pub(crate) async fn list_items(state: &AppState) -> Result<Vec<Item>, ApiError> {
+    let content_source = state.content_source.clone();
+    let response = state
+        .content_cache
+        .listing(
+            "/visual",
+            || async move { content_source.list_images().await },
+        )
+        .await?;
+
+    let images = response
+        .iter()
+        .filter_map(|value| match parse_item_data(value) {
+            Ok(item) => Some(item),
+            Err(err) => {
+                tracing::warn!("skipping gallery entry: {err}");
+                None
             }
         })
+        .collect();
+
+    Ok(images)
+}
+
+fn parse_item_data(value: &Value) -> Result<Item, ApiError> {
+    let malformed = |name: &str, reason: &str| ApiError::MalformedEntry {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let v_name = value
+        .get(0)
+        .and_then(Value::as_str)
+        .ok_or_else(|| malformed("<unnamed>", "entry is not a [name, [cid, data]] pair"))?;
+    let v_data = value
+        .get(1)
+        .and_then(|values| values.get(1))
+        .ok_or_else(|| malformed(v_name, "entry is missing its data"))?;
+    let data = serde_json::from_value::<ItemData>(v_data.clone())
+        .map_err(|err| malformed(v_name, &err.to_string()))?;
+
+    Ok(Item {
+        name: v_name.to_string(),
+        created_at: data.created_at,
+        updated_at: data.updated_at,
+    })
 }
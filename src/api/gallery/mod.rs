@@ -6,9 +6,13 @@ use tower_http::cors::{Any, CorsLayer};
 
 use crate::app::AppState;
 
+mod content_type;
 mod get_content;
 mod get_items;
 
+pub(super) use content_type::resolve as resolve_content_type;
+pub(super) use get_items::list_items;
+
 pub fn router(state: AppState) -> Router<AppState> {
     let cors_layer = CorsLayer::new()
         .allow_methods(vec![Method::GET])
@@ -19,7 +23,7 @@ pub fn router(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/", get(get_items::handler))
         .route("/:name", get(get_content::handler))
-        // TODO: get content
+        .route("/:name/", get(get_content::handler))
         .with_state(state)
         .layer(cors_layer)
 }
@@ -0,0 +1,26 @@
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+
+use crate::api::conditional::{respond, CachePolicy};
+use crate::app::AppState;
+use crate::content::highlight_stylesheet;
+
+/// `/highlight.css`, the colours for the code blocks highlighted in posts.
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/highlight.css", get(handler))
+        .with_state(state)
+}
+
+async fn handler(headers: HeaderMap) -> Response {
+    let body = highlight_stylesheet();
+    respond(
+        CachePolicy::Stylesheet,
+        &headers,
+        "text/css; charset=utf-8",
+        body,
+        None,
+    )
+}
@@ -1,13 +1,28 @@
+use std::time::Duration;
+
 use axum::Router;
-use http::header::{ACCEPT, ORIGIN};
+use http::header::{HeaderName, ACCEPT, ORIGIN, RETRY_AFTER};
 use http::Method;
 use tower_http::cors::{Any, CorsLayer};
+use url::Url;
 
 mod blog;
+mod conditional;
+mod crawl;
+mod error;
+mod feed;
 mod gallery;
+mod highlight;
+mod pagination;
+mod range;
+mod search;
 
 use crate::app::AppState;
 
+pub use crawl::router as crawl_router;
+pub use feed::router as feed_router;
+pub use highlight::router as highlight_router;
+
 pub fn router(state: AppState) -> Router<AppState> {
     let cors_layer = CorsLayer::new()
         .allow_methods(vec![Method::GET])
@@ -18,6 +33,34 @@ pub fn router(state: AppState) -> Router<AppState> {
     Router::new()
         .nest("/blog", blog::router(state.clone()))
         .nest("/gallery", gallery::router(state.clone()))
+        .nest("/search", search::router(state.clone()))
         .with_state(state)
         .layer(cors_layer)
 }
+
+/// A `Retry-After` header telling clients how many whole seconds to wait before trying again.
+fn retry_after_header(retry_after: Duration) -> (HeaderName, String) {
+    let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    (RETRY_AFTER, secs.max(1).to_string())
+}
+
+/// The absolute URL of `path` on the public site at `site_url`.
+fn absolute_url(site_url: &Url, path: &str) -> String {
+    format!("{}{}", site_url.as_str().trim_end_matches('/'), path)
+}
+
+/// Escape `text` for use in XML character data or attribute values.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,267 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// How many items a page holds when the client doesn't say.
+const DEFAULT_LIMIT: usize = 20;
+
+/// The most items a client may ask for in one page.
+const MAX_LIMIT: usize = 100;
+
+/// An item in a listing that can be sorted and paged through.
+pub(crate) trait Listed {
+    fn name(&self) -> &str;
+    fn created_at(&self) -> OffsetDateTime;
+    fn updated_at(&self) -> OffsetDateTime;
+}
+
+/// The `limit`, `cursor` and `sort` query parameters accepted by the listing endpoints.
+///
+/// `sort` is one of `created`, `updated` or `name`, optionally followed by `:asc` or `:desc`.
+/// Dates sort newest first and names alphabetically unless told otherwise. `cursor` is the
+/// `next_cursor` of the previous page, and is only meaningful with the same `sort`.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct ListParams {
+    limit: Option<usize>,
+    cursor: Option<String>,
+    sort: Option<String>,
+}
+
+/// One page of a listing, along with where the next one starts and how many items there are in all.
+#[derive(Debug, Serialize)]
+pub(crate) struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub total: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SortKey {
+    Created,
+    Updated,
+    Name,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Sort {
+    key: SortKey,
+    descending: bool,
+}
+
+impl Sort {
+    fn parse(sort: Option<&str>) -> Result<Self, PaginationError> {
+        let Some(sort) = sort else {
+            return Ok(Self {
+                key: SortKey::Created,
+                descending: true,
+            });
+        };
+
+        let (key, order) = match sort.split_once(':') {
+            Some((key, order)) => (key, Some(order)),
+            None => (sort, None),
+        };
+        let key = match key {
+            "created" => SortKey::Created,
+            "updated" => SortKey::Updated,
+            "name" => SortKey::Name,
+            _ => return Err(PaginationError::UnknownSort(sort.to_string())),
+        };
+        let descending = match order {
+            None => key != SortKey::Name,
+            Some("asc") => false,
+            Some("desc") => true,
+            Some(_) => return Err(PaginationError::UnknownSort(sort.to_string())),
+        };
+
+        Ok(Self { key, descending })
+    }
+
+    /// The position of `item` in this sort order, which is also what a cursor records.
+    fn position<T: Listed>(&self, item: &T) -> Position {
+        let timestamp = match self.key {
+            SortKey::Created => Some(item.created_at().unix_timestamp_nanos()),
+            SortKey::Updated => Some(item.updated_at().unix_timestamp_nanos()),
+            SortKey::Name => None,
+        };
+
+        Position {
+            timestamp,
+            name: item.name().to_string(),
+        }
+    }
+
+    fn cmp(&self, a: &Position, b: &Position) -> Ordering {
+        // Names break ties between equal timestamps, so every item has a distinct position.
+        let ordering = a
+            .timestamp
+            .cmp(&b.timestamp)
+            .then_with(|| a.name.cmp(&b.name));
+        match self.descending {
+            true => ordering.reverse(),
+            false => ordering,
+        }
+    }
+}
+
+/// Where an item sits in a sorted listing. Cursors are positions rather than offsets, so paging
+/// stays stable while items are added or removed.
+#[derive(Debug, PartialEq, Eq)]
+struct Position {
+    timestamp: Option<i128>,
+    name: String,
+}
+
+impl Position {
+    fn to_cursor(&self) -> String {
+        match self.timestamp {
+            Some(timestamp) => format!("{}:{}", timestamp, self.name),
+            None => format!(":{}", self.name),
+        }
+    }
+
+    fn from_cursor(cursor: &str, sort: &Sort) -> Result<Self, PaginationError> {
+        let invalid = || PaginationError::MalformedCursor(cursor.to_string());
+        let (timestamp, name) = cursor.split_once(':').ok_or_else(invalid)?;
+
+        let timestamp = match (sort.key, timestamp) {
+            (SortKey::Name, "") => None,
+            (SortKey::Created | SortKey::Updated, timestamp) => {
+                Some(timestamp.parse().map_err(|_| invalid())?)
+            }
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            timestamp,
+            name: name.to_string(),
+        })
+    }
+}
+
+/// Sort `items` as `params` asks and cut out the page it points at.
+pub(crate) fn paginate<T: Listed>(
+    mut items: Vec<T>,
+    params: &ListParams,
+) -> Result<Page<T>, PaginationError> {
+    let sort = Sort::parse(params.sort.as_deref())?;
+    let limit = match params.limit {
+        Some(0) => return Err(PaginationError::ZeroLimit),
+        Some(limit) => limit.min(MAX_LIMIT),
+        None => DEFAULT_LIMIT,
+    };
+    let after = params
+        .cursor
+        .as_deref()
+        .map(|cursor| Position::from_cursor(cursor, &sort))
+        .transpose()?;
+
+    let total = items.len();
+    items.sort_by(|a, b| sort.cmp(&sort.position(a), &sort.position(b)));
+
+    let start = match &after {
+        Some(after) => {
+            items.partition_point(|item| sort.cmp(&sort.position(item), after) != Ordering::Greater)
+        }
+        None => 0,
+    };
+    let mut items: Vec<T> = items.into_iter().skip(start).collect();
+    let next_cursor = (items.len() > limit).then(|| sort.position(&items[limit - 1]).to_cursor());
+    items.truncate(limit);
+
+    Ok(Page {
+        items,
+        next_cursor,
+        total,
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PaginationError {
+    #[error("limit must be at least 1")]
+    ZeroLimit,
+    #[error(
+        "invalid sort {0:?}, expected created, updated or name, optionally with :asc or :desc"
+    )]
+    UnknownSort(String),
+    #[error("invalid cursor {0:?}")]
+    MalformedCursor(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestItem(&'static str, i64);
+
+    impl Listed for TestItem {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn created_at(&self) -> OffsetDateTime {
+            OffsetDateTime::from_unix_timestamp(self.1).unwrap()
+        }
+
+        fn updated_at(&self) -> OffsetDateTime {
+            self.created_at()
+        }
+    }
+
+    fn items() -> Vec<TestItem> {
+        vec![
+            TestItem("b", 2),
+            TestItem("a", 3),
+            TestItem("d", 1),
+            TestItem("c", 2),
+        ]
+    }
+
+    fn names<T: Listed>(page: &Page<T>) -> Vec<&str> {
+        page.items.iter().map(Listed::name).collect()
+    }
+
+    fn params(limit: usize, cursor: Option<String>, sort: &str) -> ListParams {
+        ListParams {
+            limit: Some(limit),
+            cursor,
+            sort: Some(sort.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_pages_through_sorted_items() {
+        let first = paginate(items(), &params(3, None, "created")).unwrap();
+        assert_eq!(names(&first), ["a", "c", "b"]);
+        assert_eq!(first.total, 4);
+
+        let second = paginate(items(), &params(3, first.next_cursor, "created")).unwrap();
+        assert_eq!(names(&second), ["d"]);
+        assert_eq!(second.next_cursor, None);
+
+        let by_name = paginate(items(), &params(2, None, "name")).unwrap();
+        assert_eq!(names(&by_name), ["a", "b"]);
+        let by_name = paginate(items(), &params(2, by_name.next_cursor, "name")).unwrap();
+        assert_eq!(names(&by_name), ["c", "d"]);
+
+        let oldest_first = paginate(items(), &params(4, None, "created:asc")).unwrap();
+        assert_eq!(names(&oldest_first), ["d", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_rejects_invalid_params() {
+        assert!(matches!(
+            paginate(items(), &params(0, None, "created")),
+            Err(PaginationError::ZeroLimit)
+        ));
+        assert!(matches!(
+            paginate(items(), &params(1, None, "size")),
+            Err(PaginationError::UnknownSort(_))
+        ));
+        assert!(matches!(
+            paginate(items(), &params(1, Some(":a".to_string()), "created")),
+            Err(PaginationError::MalformedCursor(_))
+        ));
+    }
+}
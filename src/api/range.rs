@@ -0,0 +1,131 @@
+use axum::body::Body;
+use axum::http::{header, response, HeaderMap, StatusCode};
+use axum::response::Response;
+use bytes::Bytes;
+
+use super::conditional::Validators;
+
+/// The value of a request's `Range` header, if it has one we should try to honour. A range
+/// conditional on an `If-Range` that doesn't match `validators` is dropped, so the whole, current
+/// body is sent instead.
+pub(crate) fn requested_range<'a>(
+    headers: &'a HeaderMap,
+    validators: &Validators,
+) -> Option<&'a str> {
+    if !validators.range_applies(headers) {
+        return None;
+    }
+
+    headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// How to answer a range request against a body of known length.
+#[derive(Debug, PartialEq, Eq)]
+enum RangeResponse {
+    /// Send the whole body, either because no usable range was asked for or because we don't
+    /// support what was, such as multiple ranges.
+    Full,
+    /// Send the bytes from `start` to `end`, inclusive.
+    Partial { start: u64, end: u64 },
+    /// The range lies entirely past the end of the body.
+    Unsatisfiable,
+}
+
+fn resolve(range: &str, len: u64) -> RangeResponse {
+    let Some(spec) = range.trim().strip_prefix("bytes=") else {
+        return RangeResponse::Full;
+    };
+    if spec.contains(',') {
+        return RangeResponse::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return RangeResponse::Full;
+    };
+
+    let (start, end) = match (start.parse::<u64>(), end.parse::<u64>()) {
+        (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+        (Ok(start), Err(_)) if end.is_empty() => (start, len.saturating_sub(1)),
+        (Err(_), Ok(suffix)) if start.is_empty() => {
+            if suffix == 0 {
+                return RangeResponse::Unsatisfiable;
+            }
+            (len.saturating_sub(suffix), len.saturating_sub(1))
+        }
+        _ => return RangeResponse::Full,
+    };
+
+    if start >= len {
+        return RangeResponse::Unsatisfiable;
+    }
+
+    RangeResponse::Partial { start, end }
+}
+
+/// Finish `builder` with `bytes`, or the slice of them `range` asks for, setting the status and
+/// the `Accept-Ranges`, `Content-Length` and `Content-Range` headers to match.
+pub(crate) fn ranged_body(
+    builder: response::Builder,
+    range: Option<&str>,
+    bytes: Bytes,
+) -> Result<Response, axum::http::Error> {
+    let len = bytes.len() as u64;
+    let builder = builder.header(header::ACCEPT_RANGES, "bytes");
+
+    match range.map_or(RangeResponse::Full, |range| resolve(range, len)) {
+        RangeResponse::Full => builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_LENGTH, len)
+            .body(Body::from(bytes)),
+        RangeResponse::Partial { start, end } => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_LENGTH, end - start + 1)
+            .header(header::CONTENT_RANGE, format!("bytes {start}-{end}/{len}"))
+            .body(Body::from(bytes.slice(start as usize..=end as usize))),
+        RangeResponse::Unsatisfiable => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{len}"))
+            .body(Body::empty()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolves_single_byte_ranges() {
+        assert_eq!(
+            resolve("bytes=0-9", 100),
+            RangeResponse::Partial { start: 0, end: 9 }
+        );
+        assert_eq!(
+            resolve("bytes=90-200", 100),
+            RangeResponse::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            resolve("bytes=50-", 100),
+            RangeResponse::Partial { start: 50, end: 99 }
+        );
+        assert_eq!(
+            resolve("bytes=-10", 100),
+            RangeResponse::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            resolve("bytes=-500", 100),
+            RangeResponse::Partial { start: 0, end: 99 }
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_full_body_or_refuses() {
+        assert_eq!(resolve("bytes=100-", 100), RangeResponse::Unsatisfiable);
+        assert_eq!(resolve("bytes=-0", 100), RangeResponse::Unsatisfiable);
+        assert_eq!(resolve("bytes=0-", 0), RangeResponse::Unsatisfiable);
+        assert_eq!(resolve("bytes=0-1,5-6", 100), RangeResponse::Full);
+        assert_eq!(resolve("bytes=9-0", 100), RangeResponse::Full);
+        assert_eq!(resolve("items=0-9", 100), RangeResponse::Full);
+        assert_eq!(resolve("bytes=a-b", 100), RangeResponse::Full);
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::api::blog::{list_items, post_html, Item};
+use crate::api::conditional::{respond_json, CachePolicy};
+use crate::api::error::ApiError;
+use crate::app::AppState;
+use crate::search::{Document, Hit, Index};
+
+/// How many results are returned when the client doesn't say.
+const DEFAULT_LIMIT: usize = 10;
+
+/// The most results a client may ask for.
+const MAX_LIMIT: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    q: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct SearchResults {
+    query: String,
+    results: Vec<Hit>,
+}
+
+/// The posts matching every word of `q`, best match first.
+pub async fn handler(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let query = params.q.unwrap_or_default().trim().to_string();
+    if query.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Missing search query, pass it as ?q=".to_string(),
+        ));
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let index = current_index(&state).await?;
+
+    let results = SearchResults {
+        results: index.search(&query, limit),
+        query,
+    };
+    respond_json(CachePolicy::Listing, &headers, &results, None)
+}
+
+/// The search index over the current listing, rebuilt first if the listing has changed.
+pub(crate) async fn current_index(state: &AppState) -> Result<Arc<Index>, ApiError> {
+    let documents = list_items(state).await?.into_iter().map(document).collect();
+
+    let fetch_body = |name: String| post_html(state, name);
+    Ok(state.search_index.refresh(documents, fetch_body).await)
+}
+
+/// What the search index knows of `post`.
+pub(crate) fn document(post: Item) -> Document {
+    Document {
+        name: post.name,
+        title: post.title,
+        description: post.description,
+        tags: post.tags,
+        updated_at: post.updated_at,
+    }
+}
@@ -0,0 +1,24 @@
+use axum::routing::get;
+use axum::Router;
+use http::header::{ACCEPT, ORIGIN};
+use http::Method;
+use tower_http::cors::{Any, CorsLayer};
+
+use crate::app::AppState;
+
+mod get_results;
+
+pub(super) use get_results::{current_index, document};
+
+pub fn router(state: AppState) -> Router<AppState> {
+    let cors_layer = CorsLayer::new()
+        .allow_methods(vec![Method::GET])
+        .allow_headers(vec![ACCEPT, ORIGIN])
+        .allow_origin(Any)
+        .allow_credentials(false);
+
+    Router::new()
+        .route("/", get(get_results::handler))
+        .with_state(state)
+        .layer(cors_layer)
+}
@@ -1,18 +1,64 @@
 use std::env;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
 use dotenvy::dotenv;
 
 use url::Url;
 
+use crate::cache::CacheTtls;
+use crate::preview::PreviewKey;
+
+/// Which backend the site's content is served from.
+#[derive(Clone, Debug)]
+pub enum ContentBackend {
+    Leaky,
+    Local(PathBuf),
+}
+
+/// Where posts are rendered from Markdown to HTML.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkdownRenderer {
+    /// Take the HTML leaky renders.
+    Leaky,
+    /// Fetch the Markdown and render it ourselves. Local content is always rendered this way.
+    Corpo,
+}
+
 #[derive(Debug)]
 pub struct Config {
     // Listen address
     listen_addr: SocketAddr,
 
+    // Public URL of the site, used for absolute links in feeds and the sitemap
+    site_url: Url,
+
+    // Path prefixes robots.txt asks crawlers to stay out of
+    robots_disallow: Vec<String>,
+
+    // Content source Config
+    content_backend: ContentBackend,
+    markdown_renderer: MarkdownRenderer,
+
     // leaky url Config
     leaky_url: Url,
+    leaky_connect_timeout: Duration,
+    leaky_read_timeout: Duration,
+    leaky_user_agent: String,
+    leaky_max_retries: u32,
+    leaky_retry_base_delay: Duration,
+    leaky_breaker_threshold: u32,
+    leaky_breaker_cooldown: Duration,
+
+    // Preview links Config, without a secret unpublished posts can't be previewed
+    preview_key: Option<PreviewKey>,
+    preview_ttl: Duration,
+
+    // Content cache Config
+    cache_ttls: CacheTtls,
+    cache_max_bytes: usize,
 
     // Logging Level
     log_level: tracing::Level,
@@ -33,6 +79,57 @@ impl Config {
         };
         let listen_addr = listen_addr_str.parse()?;
 
+        let site_url_str = match env::var("SITE_URL") {
+            Ok(url) => url,
+            Err(_e) => {
+                tracing::warn!("No SITE_URL found in .env. Using default");
+                "https://krondor.org".to_string()
+            }
+        };
+        let site_url = Url::parse(&site_url_str)?;
+
+        let robots_disallow = match env::var("ROBOTS_DISALLOW") {
+            Ok(paths) => paths
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(_e) => {
+                tracing::warn!("No ROBOTS_DISALLOW found in .env. Using default");
+                vec!["/_status/".to_string(), "/search".to_string()]
+            }
+        };
+
+        let content_backend = match env::var("CONTENT_SOURCE").as_deref() {
+            Ok("leaky") => ContentBackend::Leaky,
+            Ok("local") => {
+                let content_dir = match env::var("CONTENT_DIR") {
+                    Ok(dir) => dir,
+                    Err(_e) => {
+                        tracing::warn!("No CONTENT_DIR found in .env. Using default");
+                        "./content".to_string()
+                    }
+                };
+                ContentBackend::Local(PathBuf::from(content_dir))
+            }
+            Ok(other) => return Err(ConfigError::InvalidContentSource(other.to_string())),
+            Err(_e) => {
+                tracing::warn!("No CONTENT_SOURCE found in .env. Using leaky");
+                ContentBackend::Leaky
+            }
+        };
+
+        let markdown_renderer = match env::var("MARKDOWN_RENDERER").as_deref() {
+            Ok("leaky") => MarkdownRenderer::Leaky,
+            Ok("corpo") => MarkdownRenderer::Corpo,
+            Ok(other) => return Err(ConfigError::InvalidMarkdownRenderer(other.to_string())),
+            Err(_e) => {
+                tracing::warn!("No MARKDOWN_RENDERER found in .env. Using leaky");
+                MarkdownRenderer::Leaky
+            }
+        };
+
         let leaky_url_str = match env::var("LEAKY_URL") {
             Ok(url) => url,
             Err(_e) => {
@@ -42,6 +139,48 @@ impl Config {
         };
         let leaky_url = Url::parse(&leaky_url_str)?;
 
+        let leaky_connect_timeout = duration_from_env("LEAKY_CONNECT_TIMEOUT_SECS", 5)?;
+        let leaky_read_timeout = duration_from_env("LEAKY_READ_TIMEOUT_SECS", 30)?;
+
+        let leaky_user_agent = match env::var("LEAKY_USER_AGENT") {
+            Ok(user_agent) => user_agent,
+            Err(_e) => format!("corpo/{}", env!("CARGO_PKG_VERSION")),
+        };
+
+        let leaky_max_retries = count_from_env("LEAKY_MAX_RETRIES", 2)?;
+        let leaky_retry_base_delay = match env::var("LEAKY_RETRY_BASE_DELAY_MS") {
+            Ok(millis) => {
+                Duration::from_millis(millis.parse().map_err(ConfigError::InvalidDuration)?)
+            }
+            Err(_e) => Duration::from_millis(100),
+        };
+        let leaky_breaker_threshold = count_from_env("LEAKY_BREAKER_THRESHOLD", 5)?;
+        let leaky_breaker_cooldown = duration_from_env("LEAKY_BREAKER_COOLDOWN_SECS", 30)?;
+
+        let preview_key = match env::var("PREVIEW_SECRET") {
+            Ok(secret) if !secret.is_empty() => Some(PreviewKey::new(&secret)),
+            _ => {
+                tracing::warn!("No PREVIEW_SECRET found in .env. Previews are disabled");
+                None
+            }
+        };
+        let preview_ttl = duration_from_env("PREVIEW_TTL_SECS", 7 * 24 * 60 * 60)?;
+
+        let cache_ttls = CacheTtls {
+            listing: duration_from_env("CACHE_LISTING_TTL_SECS", 60)?,
+            html: duration_from_env("CACHE_HTML_TTL_SECS", 300)?,
+            image: duration_from_env("CACHE_IMAGE_TTL_SECS", 3_600)?,
+            max_stale: duration_from_env("CACHE_MAX_STALE_SECS", 24 * 60 * 60)?,
+        };
+
+        let cache_max_bytes = match env::var("CACHE_MAX_BYTES") {
+            Ok(bytes) => bytes.parse().map_err(ConfigError::InvalidSize)?,
+            Err(_e) => {
+                tracing::warn!("No CACHE_MAX_BYTES found in .env. Using default");
+                64 * 1_024 * 1_024
+            }
+        };
+
         let log_level_str = match env::var("LOG_LEVEL") {
             Ok(level) => level,
             Err(_e) => {
@@ -59,7 +198,22 @@ impl Config {
 
         Ok(Config {
             listen_addr,
+            site_url,
+            robots_disallow,
+            content_backend,
+            markdown_renderer,
             leaky_url,
+            leaky_connect_timeout,
+            leaky_read_timeout,
+            leaky_user_agent,
+            leaky_max_retries,
+            leaky_retry_base_delay,
+            leaky_breaker_threshold,
+            leaky_breaker_cooldown,
+            preview_key,
+            preview_ttl,
+            cache_ttls,
+            cache_max_bytes,
             log_level,
         })
     }
@@ -68,15 +222,97 @@ impl Config {
         &self.listen_addr
     }
 
+    pub fn site_url(&self) -> &Url {
+        &self.site_url
+    }
+
+    pub fn robots_disallow(&self) -> &[String] {
+        &self.robots_disallow
+    }
+
+    pub fn content_backend(&self) -> &ContentBackend {
+        &self.content_backend
+    }
+
+    pub fn markdown_renderer(&self) -> &MarkdownRenderer {
+        &self.markdown_renderer
+    }
+
     pub fn leaky_url(&self) -> &Url {
         &self.leaky_url
     }
 
+    pub fn leaky_connect_timeout(&self) -> &Duration {
+        &self.leaky_connect_timeout
+    }
+
+    pub fn leaky_read_timeout(&self) -> &Duration {
+        &self.leaky_read_timeout
+    }
+
+    pub fn leaky_user_agent(&self) -> &str {
+        &self.leaky_user_agent
+    }
+
+    pub fn leaky_max_retries(&self) -> &u32 {
+        &self.leaky_max_retries
+    }
+
+    pub fn leaky_retry_base_delay(&self) -> &Duration {
+        &self.leaky_retry_base_delay
+    }
+
+    pub fn leaky_breaker_threshold(&self) -> &u32 {
+        &self.leaky_breaker_threshold
+    }
+
+    pub fn leaky_breaker_cooldown(&self) -> &Duration {
+        &self.leaky_breaker_cooldown
+    }
+
+    pub fn preview_key(&self) -> Option<&PreviewKey> {
+        self.preview_key.as_ref()
+    }
+
+    pub fn preview_ttl(&self) -> &Duration {
+        &self.preview_ttl
+    }
+
+    pub fn cache_ttls(&self) -> &CacheTtls {
+        &self.cache_ttls
+    }
+
+    pub fn cache_max_bytes(&self) -> &usize {
+        &self.cache_max_bytes
+    }
+
     pub fn log_level(&self) -> &tracing::Level {
         &self.log_level
     }
 }
 
+fn duration_from_env(key: &str, default_secs: u64) -> Result<Duration, ConfigError> {
+    match env::var(key) {
+        Ok(secs) => Ok(Duration::from_secs(
+            secs.parse().map_err(ConfigError::InvalidDuration)?,
+        )),
+        Err(_e) => {
+            tracing::warn!("No {key} found in .env. Using default");
+            Ok(Duration::from_secs(default_secs))
+        }
+    }
+}
+
+fn count_from_env(key: &str, default: u32) -> Result<u32, ConfigError> {
+    match env::var(key) {
+        Ok(count) => count.parse().map_err(ConfigError::InvalidCount),
+        Err(_e) => {
+            tracing::warn!("No {key} found in .env. Using default");
+            Ok(default)
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("Invalid URL: {0}")]
@@ -85,6 +321,16 @@ pub enum ConfigError {
     InvalidEnv(#[from] env::VarError),
     #[error("Invalid LogLevel: {0}")]
     InvalidLogLevel(#[from] std::num::ParseIntError),
+    #[error("Invalid Duration: {0}")]
+    InvalidDuration(std::num::ParseIntError),
+    #[error("Invalid Count: {0}")]
+    InvalidCount(std::num::ParseIntError),
+    #[error("Invalid Size: {0}")]
+    InvalidSize(std::num::ParseIntError),
+    #[error("Invalid CONTENT_SOURCE: {0} (expected leaky or local)")]
+    InvalidContentSource(String),
+    #[error("Invalid MARKDOWN_RENDERER: {0} (expected leaky or corpo)")]
+    InvalidMarkdownRenderer(String),
     #[error("Invalid SocketAddr: {0}")]
     InvalidSocketAddr(#[from] std::net::AddrParseError),
 }
@@ -1,5 +1,5 @@
 mod config;
 mod state;
 
-pub use config::Config;
+pub use config::{Config, ContentBackend, MarkdownRenderer};
 pub use state::{AppState, AppStateSetupError};
@@ -1,25 +1,61 @@
+use std::sync::Arc;
+
 use axum::extract::FromRef;
 use leptos::{get_configuration, LeptosOptions};
+use tokio::sync::watch;
 use url::Url;
 
-use super::config::Config;
+use super::config::{Config, ContentBackend, MarkdownRenderer};
+use crate::cache::ContentCache;
+use crate::content::{DynContentSource, LeakySource, LocalSource};
+use crate::health::ReadinessProbe;
+use crate::leaky::LeakyClient;
+use crate::preview::PreviewKey;
+use crate::search::SearchIndex;
+use crate::ssr::ShutdownState;
+use crate::stats::PostStatsCache;
 
 #[derive(Clone, FromRef)]
 pub struct AppState {
     pub leptos_options: LeptosOptions,
-    pub leaky_url: Url,
+    pub site_url: Url,
+    pub robots_disallow: Arc<[String]>,
+    pub content_source: DynContentSource,
+    pub content_cache: ContentCache,
+    pub readiness_probe: ReadinessProbe,
+    pub search_index: SearchIndex,
+    pub post_stats: PostStatsCache,
+    pub preview_key: Option<PreviewKey>,
 }
 
 #[allow(dead_code)]
 impl AppState {
-    pub async fn from_config(config: &Config) -> Result<Self, AppStateSetupError> {
+    pub async fn from_config(
+        config: &Config,
+        shutdown_rx: watch::Receiver<ShutdownState>,
+    ) -> Result<Self, AppStateSetupError> {
         let conf = get_configuration(None).await?;
         let leptos_options = conf.leptos_options;
-        let leaky_url = config.leaky_url().clone();
+        let content_source: DynContentSource = match config.content_backend() {
+            ContentBackend::Leaky => Arc::new(LeakySource::new(
+                LeakyClient::from_config(config)?,
+                *config.markdown_renderer() == MarkdownRenderer::Corpo,
+            )),
+            ContentBackend::Local(root) => Arc::new(LocalSource::new(root.clone())),
+        };
+        let content_cache = ContentCache::new(*config.cache_ttls(), *config.cache_max_bytes());
+        let readiness_probe = ReadinessProbe::new(content_source.clone(), shutdown_rx);
 
         Ok(Self {
             leptos_options,
-            leaky_url,
+            site_url: config.site_url().clone(),
+            robots_disallow: config.robots_disallow().into(),
+            content_source,
+            content_cache,
+            readiness_probe,
+            search_index: SearchIndex::new(),
+            post_stats: PostStatsCache::new(),
+            preview_key: config.preview_key().cloned(),
         })
     }
 }
@@ -28,4 +64,6 @@ impl AppState {
 pub enum AppStateSetupError {
     #[error("leptos config error")]
     LeptosConfigError(#[from] leptos_config::errors::LeptosConfigError),
+    #[error("failed to build leaky client: {0}")]
+    LeakyClientError(#[from] reqwest::Error),
 }
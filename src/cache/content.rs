@@ -0,0 +1,620 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bytes::{Bytes, BytesMut};
+use futures::stream::{BoxStream, Stream, StreamExt};
+use serde_json::Value;
+
+use crate::content::{Asset, ContentSourceError};
+
+/// The kinds of upstream resources we cache. Each kind gets its own time to live, listings change
+/// far more often than the images they point at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceKind {
+    Listing,
+    Html,
+    Image,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CacheTtls {
+    pub listing: Duration,
+    pub html: Duration,
+    pub image: Duration,
+    /// How long past its time to live an entry may still be served while upstream can't refresh
+    /// it. Beyond that it's treated as a miss, so callers see the upstream error rather than
+    /// arbitrarily old content.
+    pub max_stale: Duration,
+}
+
+impl CacheTtls {
+    fn get(&self, kind: ResourceKind) -> Duration {
+        match kind {
+            ResourceKind::Listing => self.listing,
+            ResourceKind::Html => self.html,
+            ResourceKind::Image => self.image,
+        }
+    }
+}
+
+/// A failed fetch, as far as the cache cares about it.
+pub trait FetchError: std::fmt::Display {
+    /// Whether the resource is gone upstream, in which case any copy we hold is dropped rather than
+    /// served on.
+    fn is_not_found(&self) -> bool;
+}
+
+impl FetchError for ContentSourceError {
+    fn is_not_found(&self) -> bool {
+        ContentSourceError::is_not_found(self)
+    }
+}
+
+#[derive(Clone)]
+enum CachedValue {
+    Listing(Arc<Vec<Value>>),
+    Bytes(Bytes),
+    Asset(Asset),
+}
+
+impl CachedValue {
+    fn weight(&self) -> usize {
+        match self {
+            CachedValue::Listing(values) => values.iter().map(|v| v.to_string().len()).sum(),
+            CachedValue::Bytes(bytes) => bytes.len(),
+            CachedValue::Asset(asset) => asset.bytes.len(),
+        }
+    }
+}
+
+struct Entry {
+    kind: ResourceKind,
+    value: CachedValue,
+    weight: usize,
+    fetched_at: Instant,
+    last_used: Instant,
+    refreshing: bool,
+}
+
+#[derive(Default)]
+struct Entries {
+    entries: HashMap<String, Entry>,
+    total_weight: usize,
+}
+
+impl Entries {
+    /// Drop `key`, returning whether there was anything to drop.
+    fn remove(&mut self, key: &str) -> bool {
+        match self.entries.remove(key) {
+            Some(entry) => {
+                self.total_weight -= entry.weight;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stale: AtomicU64,
+}
+
+/// A body being passed through [`ContentCache::tee_asset`], and what it has buffered so far.
+struct Tee {
+    cache: ContentCache,
+    kind: ResourceKind,
+    key: String,
+    content_type: Option<String>,
+    content_length: Option<u64>,
+    buffer: Option<BytesMut>,
+}
+
+impl Tee {
+    fn push(&mut self, bytes: &Bytes) {
+        let Some(buffer) = self.buffer.as_mut() else {
+            return;
+        };
+
+        if buffer.len() + bytes.len() > self.cache.max_bytes {
+            self.buffer = None;
+            return;
+        }
+        buffer.extend_from_slice(bytes);
+
+        // The server stops polling a body once it has sent the promised length, so the end of the
+        // stream may never be seen.
+        if self.content_length == Some(buffer.len() as u64) {
+            self.finish();
+        }
+    }
+
+    fn finish(&mut self) {
+        let Some(buffer) = self.buffer.take() else {
+            return;
+        };
+
+        if self
+            .content_length
+            .is_some_and(|content_length| content_length != buffer.len() as u64)
+        {
+            return;
+        }
+
+        let asset = Asset {
+            bytes: buffer.freeze(),
+            content_type: self.content_type.take(),
+        };
+        self.cache
+            .insert(self.kind, &self.key, CachedValue::Asset(asset));
+    }
+}
+
+/// An in-memory cache in front of upstream content. Fresh entries are served directly, stale
+/// entries are still served immediately while a single background task refreshes them, up to
+/// [`CacheTtls::max_stale`] past their time to live, and the least recently used entries are
+/// evicted once the memory budget is exceeded. An entry whose refresh finds it gone is dropped.
+#[derive(Clone)]
+pub struct ContentCache {
+    entries: Arc<Mutex<Entries>>,
+    counters: Arc<Counters>,
+    ttls: CacheTtls,
+    max_bytes: usize,
+}
+
+impl ContentCache {
+    pub fn new(ttls: CacheTtls, max_bytes: usize) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(Entries::default())),
+            counters: Arc::new(Counters::default()),
+            ttls,
+            max_bytes,
+        }
+    }
+
+    pub async fn listing<F, Fut, E>(&self, key: &str, fetch: F) -> Result<Arc<Vec<Value>>, E>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Vec<Value>, E>> + Send + 'static,
+        E: FetchError + Send + 'static,
+    {
+        let fetch = || async move { fetch().await.map(|v| CachedValue::Listing(Arc::new(v))) };
+
+        match self.get_or_fetch(ResourceKind::Listing, key, fetch).await? {
+            CachedValue::Listing(values) => Ok(values),
+            _ => unreachable!("listing key {key} holds a different kind of value"),
+        }
+    }
+
+    pub async fn bytes<F, Fut, E>(
+        &self,
+        kind: ResourceKind,
+        key: &str,
+        fetch: F,
+    ) -> Result<Bytes, E>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Bytes, E>> + Send + 'static,
+        E: FetchError + Send + 'static,
+    {
+        let fetch = || async move { fetch().await.map(CachedValue::Bytes) };
+
+        match self.get_or_fetch(kind, key, fetch).await? {
+            CachedValue::Bytes(bytes) => Ok(bytes),
+            _ => unreachable!("bytes key {key} holds a different kind of value"),
+        }
+    }
+
+    pub async fn asset<F, Fut, E>(
+        &self,
+        kind: ResourceKind,
+        key: &str,
+        fetch: F,
+    ) -> Result<Asset, E>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Asset, E>> + Send + 'static,
+        E: FetchError + Send + 'static,
+    {
+        let fetch = || async move { fetch().await.map(CachedValue::Asset) };
+
+        match self.get_or_fetch(kind, key, fetch).await? {
+            CachedValue::Asset(asset) => Ok(asset),
+            _ => unreachable!("asset key {key} holds a different kind of value"),
+        }
+    }
+
+    /// Look up an asset without fetching it on a miss, so the caller can stream it instead.
+    /// Stale entries are still served and refreshed in the background with `fetch`.
+    pub fn cached_asset<F, Fut, E>(&self, kind: ResourceKind, key: &str, fetch: F) -> Option<Asset>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Asset, E>> + Send + 'static,
+        E: FetchError + Send + 'static,
+    {
+        let fetch = || async move { fetch().await.map(CachedValue::Asset) };
+
+        match self.lookup(kind, key, fetch) {
+            Ok(CachedValue::Asset(asset)) => Some(asset),
+            Ok(_) => unreachable!("asset key {key} holds a different kind of value"),
+            Err(_) => None,
+        }
+    }
+
+    /// Pass a streamed body through unchanged, caching it as an asset once the whole body has
+    /// been seen. Bodies that fail part way, fall short of `content_length` or outgrow the memory
+    /// budget are not cached.
+    pub fn tee_asset<S, E>(
+        &self,
+        kind: ResourceKind,
+        key: &str,
+        content_type: Option<String>,
+        content_length: Option<u64>,
+        body: S,
+    ) -> BoxStream<'static, Result<Bytes, E>>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Send + 'static,
+    {
+        let tee = Tee {
+            cache: self.clone(),
+            kind,
+            key: key.to_string(),
+            content_type,
+            content_length,
+            buffer: Some(BytesMut::new()),
+        };
+
+        futures::stream::unfold((body.boxed(), tee), |(mut body, mut tee)| async move {
+            match body.next().await {
+                Some(Ok(bytes)) => {
+                    tee.push(&bytes);
+                    Some((Ok(bytes), (body, tee)))
+                }
+                Some(Err(err)) => {
+                    tee.buffer = None;
+                    Some((Err(err), (body, tee)))
+                }
+                None => {
+                    tee.finish();
+                    None
+                }
+            }
+        })
+        .boxed()
+    }
+
+    async fn get_or_fetch<F, Fut, E>(
+        &self,
+        kind: ResourceKind,
+        key: &str,
+        fetch: F,
+    ) -> Result<CachedValue, E>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<CachedValue, E>> + Send + 'static,
+        E: FetchError + Send + 'static,
+    {
+        match self.lookup(kind, key, fetch) {
+            Ok(value) => Ok(value),
+            Err(fetch) => {
+                let value = fetch().await?;
+                self.insert(kind, key, value.clone());
+                Ok(value)
+            }
+        }
+    }
+
+    /// Serve `key` from the cache, spawning a refresh with `fetch` if it is stale. On a miss, which
+    /// includes an entry too stale to serve at all, the unused `fetch` is handed back to the caller.
+    fn lookup<F, Fut, E>(&self, kind: ResourceKind, key: &str, fetch: F) -> Result<CachedValue, F>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<CachedValue, E>> + Send + 'static,
+        E: FetchError + Send + 'static,
+    {
+        let cached = {
+            let mut entries = self.entries.lock().unwrap();
+            let now = Instant::now();
+
+            let is_expired = entries.entries.get(key).is_some_and(|entry| {
+                now.duration_since(entry.fetched_at)
+                    >= self.ttls.get(entry.kind) + self.ttls.max_stale
+            });
+            if is_expired {
+                tracing::debug!(key, "dropping cache entry past its maximum staleness");
+                entries.remove(key);
+            }
+
+            entries.entries.get_mut(key).map(|entry| {
+                entry.last_used = now;
+
+                let is_stale = now.duration_since(entry.fetched_at) >= self.ttls.get(entry.kind);
+                let needs_refresh = is_stale && !entry.refreshing;
+                if needs_refresh {
+                    entry.refreshing = true;
+                }
+
+                (entry.value.clone(), is_stale, needs_refresh)
+            })
+        };
+
+        match cached {
+            Some((value, false, _)) => {
+                self.record(key, &self.counters.hits, "hit");
+                Ok(value)
+            }
+            Some((value, true, needs_refresh)) => {
+                self.record(key, &self.counters.stale, "stale");
+
+                if needs_refresh {
+                    let cache = self.clone();
+                    let key = key.to_string();
+
+                    tokio::spawn(async move {
+                        match fetch().await {
+                            Ok(value) => cache.insert(kind, &key, value),
+                            Err(err) if err.is_not_found() => {
+                                tracing::debug!(key, "dropping cache entry gone upstream");
+                                cache.entries.lock().unwrap().remove(&key);
+                            }
+                            Err(err) => {
+                                tracing::warn!(key, "failed to refresh stale cache entry: {err}");
+                                cache.finish_refresh(&key);
+                            }
+                        }
+                    });
+                }
+
+                Ok(value)
+            }
+            None => {
+                self.record(key, &self.counters.misses, "miss");
+                Err(fetch)
+            }
+        }
+    }
+
+    fn insert(&self, kind: ResourceKind, key: &str, value: CachedValue) {
+        let weight = value.weight();
+        let mut entries = self.entries.lock().unwrap();
+
+        entries.remove(key);
+
+        if weight > self.max_bytes {
+            tracing::debug!(
+                key,
+                weight,
+                "not caching entry larger than the memory budget"
+            );
+            return;
+        }
+
+        while entries.total_weight + weight > self.max_bytes {
+            let Some(lru_key) = entries
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+
+            if entries.remove(&lru_key) {
+                tracing::debug!(key = lru_key, "evicted cache entry");
+            }
+        }
+
+        let now = Instant::now();
+        entries.total_weight += weight;
+        entries.entries.insert(
+            key.to_string(),
+            Entry {
+                kind,
+                value,
+                weight,
+                fetched_at: now,
+                last_used: now,
+                refreshing: false,
+            },
+        );
+    }
+
+    fn finish_refresh(&self, key: &str) {
+        if let Some(entry) = self.entries.lock().unwrap().entries.get_mut(key) {
+            entry.refreshing = false;
+        }
+    }
+
+    fn record(&self, key: &str, counter: &AtomicU64, outcome: &'static str) {
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        tracing::debug!(
+            key,
+            outcome,
+            hits = self.counters.hits.load(Ordering::Relaxed),
+            misses = self.counters.misses.load(Ordering::Relaxed),
+            stale = self.counters.stale.load(Ordering::Relaxed),
+            "content cache lookup"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    impl FetchError for Infallible {
+        fn is_not_found(&self) -> bool {
+            match *self {}
+        }
+    }
+
+    impl FetchError for &str {
+        fn is_not_found(&self) -> bool {
+            false
+        }
+    }
+
+    fn ttls(ttl: Duration) -> CacheTtls {
+        CacheTtls {
+            listing: ttl,
+            html: ttl,
+            image: ttl,
+            max_stale: Duration::from_secs(60),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serves_fresh_entries_from_cache() {
+        let cache = ContentCache::new(ttls(Duration::from_secs(60)), 1_024);
+
+        let first = cache
+            .bytes(ResourceKind::Html, "/a", || async {
+                Ok::<_, Infallible>(Bytes::from_static(b"first"))
+            })
+            .await
+            .unwrap();
+        let second = cache
+            .bytes(ResourceKind::Html, "/a", || async {
+                Ok::<_, Infallible>(Bytes::from_static(b"second"))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_serves_stale_entries_while_refreshing() {
+        let cache = ContentCache::new(ttls(Duration::ZERO), 1_024);
+
+        cache
+            .bytes(ResourceKind::Html, "/a", || async {
+                Ok::<_, Infallible>(Bytes::from_static(b"first"))
+            })
+            .await
+            .unwrap();
+        let stale = cache
+            .bytes(ResourceKind::Html, "/a", || async {
+                Ok::<_, Infallible>(Bytes::from_static(b"second"))
+            })
+            .await
+            .unwrap();
+        assert_eq!(&stale[..], b"first");
+
+        tokio::task::yield_now().await;
+        let refreshed = cache
+            .bytes(ResourceKind::Html, "/a", || async {
+                Ok::<_, Infallible>(Bytes::from_static(b"third"))
+            })
+            .await
+            .unwrap();
+        assert_eq!(&refreshed[..], b"second");
+    }
+
+    #[tokio::test]
+    async fn test_drops_entries_too_stale_or_gone() {
+        let cache = ContentCache::new(
+            CacheTtls {
+                max_stale: Duration::ZERO,
+                ..ttls(Duration::ZERO)
+            },
+            1_024,
+        );
+        cache
+            .bytes(ResourceKind::Html, "/a", || async {
+                Ok::<_, Infallible>(Bytes::from_static(b"first"))
+            })
+            .await
+            .unwrap();
+        let expired = cache
+            .bytes(ResourceKind::Html, "/a", || async {
+                Err::<Bytes, _>("upstream down")
+            })
+            .await;
+        assert!(expired.is_err());
+
+        let cache = ContentCache::new(ttls(Duration::ZERO), 1_024);
+        cache
+            .bytes(ResourceKind::Html, "/b", || async {
+                Ok::<_, Infallible>(Bytes::from_static(b"first"))
+            })
+            .await
+            .unwrap();
+        let stale = cache
+            .bytes(ResourceKind::Html, "/b", || async {
+                Err::<Bytes, _>(ContentSourceError::NotFound)
+            })
+            .await
+            .unwrap();
+        assert_eq!(&stale[..], b"first");
+
+        tokio::task::yield_now().await;
+        let entries = cache.entries.lock().unwrap();
+        assert!(!entries.entries.contains_key("/b"));
+        assert_eq!(entries.total_weight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() {
+        let cache = ContentCache::new(ttls(Duration::from_secs(60)), 8);
+
+        for key in ["/a", "/b"] {
+            cache
+                .bytes(ResourceKind::Image, key, || async {
+                    Ok::<_, Infallible>(Bytes::from_static(b"1234"))
+                })
+                .await
+                .unwrap();
+        }
+        cache
+            .bytes(ResourceKind::Image, "/c", || async {
+                Ok::<_, Infallible>(Bytes::from_static(b"1234"))
+            })
+            .await
+            .unwrap();
+
+        let entries = cache.entries.lock().unwrap();
+        assert!(!entries.entries.contains_key("/a"));
+        assert!(entries.entries.contains_key("/c"));
+        assert_eq!(entries.total_weight, 8);
+    }
+
+    #[tokio::test]
+    async fn test_caches_streamed_assets_once_complete() {
+        let cache = ContentCache::new(ttls(Duration::from_secs(60)), 1_024);
+        let missing = || async { Err::<Asset, _>("not cached") };
+        let chunks = || {
+            futures::stream::iter([
+                Ok::<_, Infallible>(Bytes::from_static(b"ab")),
+                Ok(Bytes::from_static(b"cd")),
+            ])
+        };
+
+        let short = cache.tee_asset(ResourceKind::Image, "/short", None, Some(5), chunks());
+        let _: Vec<_> = short.collect().await;
+        assert!(cache
+            .cached_asset(ResourceKind::Image, "/short", missing)
+            .is_none());
+
+        let mut body = cache.tee_asset(ResourceKind::Image, "/a", None, Some(4), chunks());
+        body.next().await;
+        assert!(cache
+            .cached_asset(ResourceKind::Image, "/a", missing)
+            .is_none());
+        body.next().await;
+
+        let asset = cache
+            .cached_asset(ResourceKind::Image, "/a", missing)
+            .unwrap();
+        assert_eq!(&asset.bytes[..], b"abcd");
+    }
+}
@@ -0,0 +1,3 @@
+mod content;
+
+pub use content::{CacheTtls, ContentCache, ResourceKind};
@@ -0,0 +1,299 @@
+use std::cell::RefCell;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+use bytes::Bytes;
+use lol_html::errors::RewritingError;
+use lol_html::html_content::{ContentType, Element, TextChunk};
+use lol_html::{element, text, RewriteStrSettings};
+use syntect::highlighting::{Color, Theme, ThemeSet};
+use syntect::html::{css_for_theme_with_class_style, line_tokens_to_classed_spans, ClassStyle};
+use syntect::parsing::{ParseState, ScopeStack, ScopeStackOp, SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+use super::text::decode_entities;
+
+/// Token classes are prefixed so they can't collide with the site's own.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+/// Layout shared by both themes. Lines are wrapped in elements of their own so they can be
+/// numbered and marked without any script.
+const LAYOUT_CSS: &str = "\
+pre:has(> code.hl-code) { padding: 0; }
+code.hl-code { display: inline-block; min-width: 100%; padding: 1rem 0; }
+code.hl-code .code-line { display: inline-block; box-sizing: border-box; width: 100%; \
+min-height: 1lh; padding: 0 1rem; vertical-align: top; }
+code.hl-code.line-numbers { counter-reset: code-line; }
+code.hl-code.line-numbers .code-line::before { counter-increment: code-line; \
+content: counter(code-line); display: inline-block; width: 2em; margin-right: 1em; \
+text-align: right; opacity: 0.5; user-select: none; }
+";
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+static STYLESHEET: LazyLock<String> = LazyLock::new(|| {
+    let themes = ThemeSet::load_defaults();
+    format!(
+        "{LAYOUT_CSS}\n{}\n@media (prefers-color-scheme: dark) {{\n{}}}\n",
+        theme_css(&themes.themes[LIGHT_THEME]),
+        theme_css(&themes.themes[DARK_THEME]),
+    )
+});
+
+/// The stylesheet for highlighted code: a light theme, and a dark one for readers who prefer it.
+pub fn highlight_stylesheet() -> &'static str {
+    &STYLESHEET
+}
+
+/// Highlight every `<pre><code>` block in a post's HTML that names its language with a
+/// `language-*` class, wrapping its tokens in `hl-` prefixed classes for [`highlight_stylesheet`] to colour.
+///
+/// A block can also ask for numbered lines with a `data-linenos` attribute, and for lines to be
+/// marked with `data-hl-lines`, a comma separated list of line numbers and ranges like `2-3,5`.
+/// HTML that can't be rewritten is returned as it was.
+pub fn highlight_code_blocks(html: Bytes) -> Bytes {
+    let Ok(source) = std::str::from_utf8(&html) else {
+        return html;
+    };
+
+    match rewrite(source) {
+        Ok(rewritten) => Bytes::from(rewritten),
+        Err(err) => {
+            tracing::warn!("failed to highlight code blocks: {err}");
+            html
+        }
+    }
+}
+
+/// A code block being read, and how it asked to be highlighted.
+#[derive(Debug, Default)]
+struct CodeBlock {
+    language: Option<String>,
+    line_numbers: bool,
+    marked_lines: Vec<RangeInclusive<usize>>,
+    /// The block's text as written in the HTML, entities and all.
+    raw: String,
+}
+
+fn rewrite(html: &str) -> Result<String, RewritingError> {
+    let current: Rc<RefCell<Option<CodeBlock>>> = Rc::default();
+    let on_code = {
+        let current = current.clone();
+        move |code: &mut Element| {
+            let Some(block) = CodeBlock::for_element(code) else {
+                return Ok(());
+            };
+            let Some(end_tag_handlers) = code.end_tag_handlers() else {
+                return Ok(());
+            };
+
+            // The text is collected as it streams past, and swapped for its highlighted form
+            // just before the closing tag.
+            let current_at_end = current.clone();
+            end_tag_handlers.push(Box::new(move |end| {
+                if let Some(block) = current_at_end.borrow_mut().take() {
+                    end.before(&block.render(), ContentType::Html);
+                }
+                Ok(())
+            }));
+
+            let mut class = code.get_attribute("class").unwrap_or_default();
+            class.push_str(" hl-code");
+            if block.line_numbers {
+                class.push_str(" line-numbers");
+            }
+            code.set_attribute("class", class.trim_start())?;
+            *current.borrow_mut() = Some(block);
+            Ok(())
+        }
+    };
+    let on_text = move |chunk: &mut TextChunk| {
+        if let Some(block) = current.borrow_mut().as_mut() {
+            block.raw.push_str(chunk.as_str());
+            chunk.remove();
+        }
+        Ok(())
+    };
+
+    lol_html::rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![
+                element!("pre > code", on_code),
+                text!("pre > code", on_text),
+            ],
+            ..RewriteStrSettings::default()
+        },
+    )
+}
+
+impl CodeBlock {
+    /// How the `<code>` element `code` asks to be highlighted, or `None` if it doesn't.
+    fn for_element(code: &Element) -> Option<Self> {
+        let language = code.get_attribute("class").and_then(|class| {
+            class
+                .split_ascii_whitespace()
+                .find_map(|class| class.strip_prefix("language-"))
+                .map(str::to_string)
+        });
+        let line_numbers = code.has_attribute("data-linenos");
+        let marked_lines = code
+            .get_attribute("data-hl-lines")
+            .map(|lines| parse_line_ranges(&lines))
+            .unwrap_or_default();
+
+        if language.is_none() && !line_numbers && marked_lines.is_empty() {
+            return None;
+        }
+        Some(CodeBlock {
+            language,
+            line_numbers,
+            marked_lines,
+            raw: String::new(),
+        })
+    }
+
+    fn render(&self) -> String {
+        let code = decode_entities(&self.raw);
+        // Unknown languages are still split into lines, so they can be numbered and marked.
+        let syntax = self
+            .language
+            .as_deref()
+            .and_then(|language| SYNTAXES.find_syntax_by_token(language))
+            .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+
+        match highlight(&code, syntax, &self.marked_lines) {
+            Ok(html) => html,
+            Err(err) => {
+                tracing::warn!("failed to highlight a {} block: {err}", syntax.name);
+                self.raw.clone()
+            }
+        }
+    }
+}
+
+/// `code` as a run of `code-line` elements, each holding its line's classed tokens.
+fn highlight(
+    code: &str,
+    syntax: &SyntaxReference,
+    marked_lines: &[RangeInclusive<usize>],
+) -> Result<String, syntect::Error> {
+    let mut parse_state = ParseState::new(syntax);
+    let mut scopes = ScopeStack::new();
+    let mut html = String::with_capacity(code.len() * 2);
+
+    for (index, line) in LinesWithEndings::from(code).enumerate() {
+        let ops = parse_state.parse_line(line, &SYNTAXES)?;
+
+        // Tokens can run over several lines, like a block comment. Their spans are closed at the
+        // end of each line and opened again at the start of the next so lines nest cleanly.
+        let reopen: Vec<(usize, ScopeStackOp)> = scopes
+            .as_slice()
+            .iter()
+            .map(|scope| (0, ScopeStackOp::Push(*scope)))
+            .collect();
+        let (opening, _) =
+            line_tokens_to_classed_spans("", &reopen, CLASS_STYLE, &mut ScopeStack::new())?;
+
+        let content = line.trim_end_matches(['\n', '\r']);
+        let ops: Vec<(usize, ScopeStackOp)> = ops
+            .into_iter()
+            .map(|(offset, op)| (offset.min(content.len()), op))
+            .collect();
+        let (tokens, _) = line_tokens_to_classed_spans(content, &ops, CLASS_STYLE, &mut scopes)?;
+
+        let number = index + 1;
+        let class = if marked_lines.iter().any(|lines| lines.contains(&number)) {
+            "code-line marked"
+        } else {
+            "code-line"
+        };
+        html.push_str(&format!(
+            "<span class=\"{class}\">{opening}{tokens}{}</span>\n",
+            "</span>".repeat(scopes.len())
+        ));
+    }
+
+    Ok(html)
+}
+
+/// Line numbers and ranges like `2-3,5`. Anything that isn't one is skipped.
+fn parse_line_ranges(lines: &str) -> Vec<RangeInclusive<usize>> {
+    lines
+        .split(',')
+        .filter_map(|range| {
+            let range = range.trim();
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            Some(start.trim().parse().ok()?..=end.trim().parse().ok()?)
+        })
+        .collect()
+}
+
+/// The classes syntect generates for `theme`, along with the colours of the block around them and
+/// of marked lines.
+fn theme_css(theme: &Theme) -> String {
+    let mut css = css_for_theme_with_class_style(theme, CLASS_STYLE)
+        .expect("writing CSS to a string can't fail");
+
+    if let Some(background) = theme.settings.background {
+        css.push_str(&format!(
+            "pre:has(> code.hl-code) {{ background-color: {}; }}\n",
+            hex(background)
+        ));
+    }
+    let marked = theme
+        .settings
+        .line_highlight
+        .map_or_else(|| "rgba(128, 128, 128, 0.2)".to_string(), hex);
+    css.push_str(&format!(
+        "code.hl-code .code-line.marked {{ background-color: {marked}; }}\n"
+    ));
+    css
+}
+
+fn hex(color: Color) -> String {
+    format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        color.r, color.g, color.b, color.a
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlights_code_blocks() {
+        let html = concat!(
+            "<p>x</p><pre><code class=\"language-rust\" data-linenos=\"\" data-hl-lines=\"2\">",
+            "/* a\nb */\nlet s = &quot;&lt;&quot;;\n",
+            "</code></pre><pre><code>plain &amp; simple</code></pre>",
+        );
+        let highlighted = highlight_code_blocks(Bytes::from(html));
+        let highlighted = std::str::from_utf8(&highlighted).unwrap();
+
+        assert!(highlighted
+            .starts_with("<p>x</p><pre><code class=\"language-rust hl-code line-numbers\""));
+        // The comment's span is closed and reopened around the line break.
+        assert!(highlighted.contains(
+            "<span class=\"code-line\"><span class=\"hl-source hl-rust\">\
+             <span class=\"hl-comment hl-block hl-rust\">\
+             <span class=\"hl-punctuation hl-definition hl-comment hl-rust\">/*</span> a</span>\
+             </span></span>\n\
+             <span class=\"code-line marked\"><span class=\"hl-source hl-rust\">\
+             <span class=\"hl-comment hl-block hl-rust\">b \
+             <span class=\"hl-punctuation hl-definition hl-comment hl-rust\">*/</span>"
+        ));
+        assert!(highlighted.contains("&quot;</span>&lt;<span"));
+        assert!(highlighted.ends_with("<pre><code>plain &amp; simple</code></pre>"));
+    }
+
+    #[test]
+    fn test_parses_line_ranges() {
+        assert_eq!(parse_line_ranges("1, 3-4,x,6-"), vec![1..=1, 3..=4]);
+    }
+}
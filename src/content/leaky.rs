@@ -0,0 +1,81 @@
+use axum::async_trait;
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+use serde_json::Value;
+
+use super::markdown::{self, split_front_matter};
+use super::{Asset, AssetStream, ContentSource, ContentSourceError};
+use crate::leaky::{BreakerState, LeakyClient};
+
+/// Serves content from leaky's `/writing` and `/visual` endpoints.
+pub struct LeakySource {
+    leaky_client: LeakyClient,
+    /// Fetch posts as Markdown and render them ourselves, rather than taking leaky's HTML.
+    render_markdown: bool,
+}
+
+impl LeakySource {
+    pub fn new(leaky_client: LeakyClient, render_markdown: bool) -> Self {
+        Self {
+            leaky_client,
+            render_markdown,
+        }
+    }
+}
+
+#[async_trait]
+impl ContentSource for LeakySource {
+    async fn list_posts(&self) -> Result<Vec<Value>, ContentSourceError> {
+        Ok(self.leaky_client.list_writing().await?)
+    }
+
+    async fn get_post(&self, name: &str) -> Result<Bytes, ContentSourceError> {
+        if !self.render_markdown {
+            return Ok(self.leaky_client.get_writing_html(name).await?);
+        }
+
+        let source = self.leaky_client.get_writing_markdown(name).await?;
+        let (_, body) = split_front_matter(&source);
+        Ok(Bytes::from(markdown::render(body)))
+    }
+
+    async fn list_images(&self) -> Result<Vec<Value>, ContentSourceError> {
+        Ok(self.leaky_client.list_visual().await?)
+    }
+
+    async fn get_image(&self, name: &str) -> Result<Asset, ContentSourceError> {
+        let body = self.leaky_client.get_visual(name).await?;
+
+        Ok(Asset {
+            bytes: body.bytes,
+            content_type: body.content_type,
+        })
+    }
+
+    async fn stream_image(
+        &self,
+        name: &str,
+        range: Option<&str>,
+    ) -> Result<AssetStream, ContentSourceError> {
+        let stream = self.leaky_client.stream_visual(name, range).await?;
+
+        Ok(AssetStream {
+            content_type: stream.content_type,
+            content_length: stream.content_length,
+            content_range: stream.content_range,
+            body: stream.body.map_err(ContentSourceError::from).boxed(),
+        })
+    }
+
+    async fn check(&self) -> Result<(), ContentSourceError> {
+        // No point probing while the breaker has us failing every request anyway.
+        match self.leaky_client.breaker_state() {
+            BreakerState::Open => Err(ContentSourceError::Unavailable),
+            BreakerState::Closed | BreakerState::HalfOpen => Ok(self.leaky_client.probe().await?),
+        }
+    }
+
+    fn breaker_state(&self) -> Option<BreakerState> {
+        Some(self.leaky_client.breaker_state())
+    }
+}
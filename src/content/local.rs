@@ -0,0 +1,315 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use axum::async_trait;
+use bytes::Bytes;
+use serde_json::{Map, Value};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use super::markdown::{self, split_front_matter};
+use super::{Asset, ContentSource, ContentSourceError};
+use crate::slug::Slug;
+
+const WRITING_DIR: &str = "writing";
+const VISUAL_DIR: &str = "visual";
+const MARKDOWN_EXTENSION: &str = "md";
+
+/// Serves content from a directory on disk, laid out as:
+///
+/// ```text
+/// <root>/writing/<name>.md   markdown posts, each starting with a YAML front matter block
+/// <root>/visual/<name>       image files, served as-is
+/// ```
+///
+/// File names must already be canonical slugs (lowercase, no spaces), since that's the only form a
+/// page will ask for; anything else is skipped with a warning. Post timestamps come from
+/// `created_at` and `updated_at` in the front matter when present (as RFC 3339 strings), and from
+/// the file's modification time otherwise. A missing `description` is treated as empty.
+pub struct LocalSource {
+    root: PathBuf,
+}
+
+impl LocalSource {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Resolve `name` inside one of our content directories, refusing anything that could escape it.
+    fn path(&self, dir: &str, name: &str) -> Result<PathBuf, ContentSourceError> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(ContentSourceError::NotFound);
+        }
+
+        Ok(self.root.join(dir).join(name))
+    }
+
+    async fn list_dir(&self, dir: &str) -> Result<Vec<(String, PathBuf)>, ContentSourceError> {
+        let mut read_dir = tokio::fs::read_dir(self.root.join(dir)).await?;
+        let mut files = Vec::new();
+
+        while let Some(dir_entry) = read_dir.next_entry().await? {
+            let path = dir_entry.path();
+            let is_hidden = dir_entry.file_name().to_string_lossy().starts_with('.');
+
+            if dir_entry.file_type().await?.is_file() && !is_hidden {
+                files.push((dir_entry.file_name().to_string_lossy().to_string(), path));
+            }
+        }
+
+        Ok(files)
+    }
+}
+
+#[async_trait]
+impl ContentSource for LocalSource {
+    async fn list_posts(&self) -> Result<Vec<Value>, ContentSourceError> {
+        let mut entries = Vec::new();
+
+        for (_, path) in self.list_dir(WRITING_DIR).await? {
+            if path.extension().and_then(|ext| ext.to_str()) != Some(MARKDOWN_EXTENSION) {
+                continue;
+            }
+
+            let Some(name) = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .filter(|name| is_servable(name, &path))
+            else {
+                continue;
+            };
+
+            // One broken post shouldn't take the whole listing down with it.
+            match post_entry(name, &path).await {
+                Ok(entry) => entries.push(entry),
+                Err(err) => tracing::warn!("skipping {}: {err}", path.display()),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn get_post(&self, name: &str) -> Result<Bytes, ContentSourceError> {
+        let path = self.path(WRITING_DIR, &format!("{name}.{MARKDOWN_EXTENSION}"))?;
+        let source = tokio::fs::read_to_string(path).await.map_err(not_found)?;
+
+        let (_, body) = split_front_matter(&source);
+        Ok(Bytes::from(markdown::render(body)))
+    }
+
+    async fn list_images(&self) -> Result<Vec<Value>, ContentSourceError> {
+        let mut entries = Vec::new();
+
+        for (name, path) in self.list_dir(VISUAL_DIR).await? {
+            if !is_servable(&name, &path) {
+                continue;
+            }
+
+            let modified_at = modified_at(&path).await?;
+            entries.push(manifest_entry(name, modified_at, modified_at, Map::new()));
+        }
+
+        Ok(entries)
+    }
+
+    async fn get_image(&self, name: &str) -> Result<Asset, ContentSourceError> {
+        let path = self.path(VISUAL_DIR, name)?;
+        let bytes = tokio::fs::read(path).await.map_err(not_found)?;
+
+        // Leave the type to be sniffed from the bytes, the file extension is only a guess.
+        Ok(Asset {
+            bytes: Bytes::from(bytes),
+            content_type: None,
+        })
+    }
+
+    async fn check(&self) -> Result<(), ContentSourceError> {
+        match tokio::fs::metadata(&self.root).await {
+            Ok(metadata) if metadata.is_dir() => Ok(()),
+            _ => Err(ContentSourceError::Unavailable),
+        }
+    }
+}
+
+fn not_found(err: std::io::Error) -> ContentSourceError {
+    match err.kind() {
+        ErrorKind::NotFound => ContentSourceError::NotFound,
+        _ => ContentSourceError::LocalReadFailed(err),
+    }
+}
+
+/// Whether `name` can be asked for as-is. A name that only canonicalizes to a slug would be
+/// redirected to a route that doesn't match the file, so it's as unreachable as an invalid one.
+fn is_servable(name: &str, path: &Path) -> bool {
+    if Slug::is_canonical(name) {
+        return true;
+    }
+
+    match Slug::canonicalize(name) {
+        Ok(slug) => tracing::warn!(
+            "skipping {}: rename it to {slug} to serve it",
+            path.display()
+        ),
+        Err(err) => tracing::warn!("skipping {}: {err}", path.display()),
+    }
+    false
+}
+
+/// The manifest entry of the markdown post at `path`.
+async fn post_entry(name: String, path: &Path) -> Result<Value, ContentSourceError> {
+    let source = tokio::fs::read_to_string(path).await?;
+    let (front_matter, _) = split_front_matter(&source);
+    let mut metadata = parse_front_matter(&name, front_matter)?;
+    metadata
+        .entry("description")
+        .or_insert_with(|| Value::String(String::new()));
+
+    let modified_at = modified_at(path).await?;
+    let created_at = timestamp(&metadata, "created_at", path).unwrap_or(modified_at);
+    let updated_at = timestamp(&metadata, "updated_at", path).unwrap_or(modified_at);
+
+    Ok(manifest_entry(name, created_at, updated_at, metadata))
+}
+
+async fn modified_at(path: &Path) -> Result<OffsetDateTime, ContentSourceError> {
+    let modified = tokio::fs::metadata(path).await?.modified()?;
+    Ok(OffsetDateTime::from(modified))
+}
+
+/// The front matter timestamp under `key`, if there is one. One that's there but isn't RFC 3339
+/// (say an unquoted `2024-04-09`) is warned about rather than silently ignored.
+fn timestamp(metadata: &Map<String, Value>, key: &str, path: &Path) -> Option<OffsetDateTime> {
+    let value = metadata.get(key)?;
+    let parsed = value
+        .as_str()
+        .and_then(|value| OffsetDateTime::parse(value, &Rfc3339).ok());
+
+    if parsed.is_none() {
+        tracing::warn!(
+            "{}: {key} {value} is not an RFC 3339 timestamp, using the file's modification time",
+            path.display()
+        );
+    }
+    parsed
+}
+
+fn manifest_entry(
+    name: String,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    metadata: Map<String, Value>,
+) -> Value {
+    serde_json::json!([
+        name,
+        [
+            Value::Null,
+            {
+                "created_at": created_at,
+                "updated_at": updated_at,
+                "metadata": metadata,
+            }
+        ]
+    ])
+}
+
+fn parse_front_matter(
+    name: &str,
+    front_matter: Option<&str>,
+) -> Result<Map<String, Value>, ContentSourceError> {
+    let invalid = |reason: String| ContentSourceError::InvalidFrontMatter {
+        name: name.to_string(),
+        reason,
+    };
+
+    match front_matter {
+        None => Ok(Map::new()),
+        Some(front_matter) if front_matter.trim().is_empty() => Ok(Map::new()),
+        Some(front_matter) => match serde_yaml::from_str::<Value>(front_matter) {
+            Ok(Value::Object(metadata)) => Ok(metadata),
+            Ok(_) => Err(invalid("front matter is not a mapping".to_string())),
+            Err(err) => Err(invalid(err.to_string())),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_front_matter() {
+        let source = "---\ntitle: Hello\ndescription: World\n---\n# Hello\n";
+        let (front_matter, body) = split_front_matter(source);
+
+        assert_eq!(front_matter, Some("title: Hello\ndescription: World\n"));
+        assert_eq!(body, "# Hello\n");
+
+        let metadata = parse_front_matter("hello", front_matter).unwrap();
+        assert_eq!(metadata["title"], "Hello");
+
+        assert_eq!(split_front_matter("# Hello\n"), (None, "# Hello\n"));
+    }
+
+    #[test]
+    fn test_manifest_entry_matches_leaky_shape() {
+        let created_at = OffsetDateTime::parse("2024-04-09T12:30:00Z", &Rfc3339).unwrap();
+        let entry = manifest_entry("hello".to_string(), created_at, created_at, Map::new());
+
+        assert_eq!(entry[0], "hello");
+        assert_eq!(
+            entry[1][1]["created_at"],
+            serde_json::json!([2024, 100, 12, 30, 0, 0, 0, 0, 0])
+        );
+    }
+
+    #[test]
+    fn test_rejects_names_outside_root() {
+        let source = LocalSource::new(PathBuf::from("/srv/content"));
+
+        assert!(source.path(WRITING_DIR, "../secrets").is_err());
+        assert!(source.path(WRITING_DIR, ".env").is_err());
+        assert!(source.path(WRITING_DIR, "hello-world").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_lists_posts_around_broken_ones() {
+        let root = std::env::temp_dir().join(format!("corpo-local-{}", std::process::id()));
+        let writing = root.join(WRITING_DIR);
+        std::fs::create_dir_all(&writing).unwrap();
+        std::fs::write(writing.join("good.md"), "---\ntitle: Good\n---\nText\n").unwrap();
+        std::fs::write(writing.join("bad.md"), "---\ntitle: [unclosed\n---\nText\n").unwrap();
+        std::fs::write(writing.join("binary.md"), [0xff, 0xfe, 0x00]).unwrap();
+        std::fs::write(writing.join("My-Post.md"), "---\ntitle: Upper\n---\nText\n").unwrap();
+        std::fs::write(writing.join("my post.md"), "---\ntitle: Space\n---\nText\n").unwrap();
+
+        let entries = LocalSource::new(root.clone()).list_posts().await;
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let entries = entries.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0][0], "good");
+    }
+
+    #[tokio::test]
+    async fn test_post_entry_defaults_local_metadata() {
+        let root = std::env::temp_dir().join(format!("corpo-local-entry-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let path = root.join("hello.md");
+        std::fs::write(
+            &path,
+            "---\ntitle: Hello\ncreated_at: 2024-04-09\n---\nText\n",
+        )
+        .unwrap();
+
+        let entry = post_entry("hello".to_string(), &path).await;
+        let modified_at = modified_at(&path).await.unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let entry = entry.unwrap();
+        assert_eq!(entry[1][1]["metadata"]["description"], "");
+        assert_eq!(
+            entry[1][1]["created_at"],
+            serde_json::to_value(modified_at).unwrap()
+        );
+    }
+}
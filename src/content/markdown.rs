@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+
+/// Render a Markdown document to HTML.
+///
+/// On top of CommonMark this supports GFM tables, task lists, strikethrough and footnotes. Every
+/// heading gets an `id` to link to, either the one given with `{#id}` or one derived from its
+/// text, kept unique within the document.
+///
+/// A fenced code block can follow its language with `linenos` to number its lines, and with
+/// `hl_lines=2-3,5` to mark some of them, as in ```` ```rust linenos hl_lines=2 ````.
+pub fn render(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+
+    let mut events: Vec<Event> = Parser::new_ext(markdown, options).collect();
+    assign_heading_ids(&mut events);
+    annotate_code_blocks(&mut events);
+
+    let mut output = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut output, events.into_iter());
+    output
+}
+
+/// Split a `---` delimited front matter block off the top of a markdown document.
+pub fn split_front_matter(source: &str) -> (Option<&str>, &str) {
+    let Some(rest) = source
+        .strip_prefix("---\n")
+        .or_else(|| source.strip_prefix("---\r\n"))
+    else {
+        return (None, source);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+
+    (None, source)
+}
+
+fn assign_heading_ids(events: &mut [Event]) {
+    // Explicit ids are claimed first, so a derived id never takes one an author chose.
+    let mut used: HashSet<String> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Tag::Heading { id: Some(id), .. }) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    let mut start = None;
+    let mut text = String::new();
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(Tag::Heading { id: None, .. }) => {
+                start = Some(i);
+                text.clear();
+            }
+            Event::Text(fragment) | Event::Code(fragment) if start.is_some() => {
+                text.push_str(fragment)
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let Some(start) = start.take() else {
+                    continue;
+                };
+                let id = unique_id(&slugify(&text), &mut used);
+                if let Event::Start(Tag::Heading { id: heading_id, .. }) = &mut events[start] {
+                    *heading_id = Some(CowStr::from(id));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Carry the options after a fenced code block's language over to its `<code>` element as
+/// `data-linenos` and `data-hl-lines`, where highlighting picks them up.
+fn annotate_code_blocks(events: &mut [Event]) {
+    for event in events {
+        let Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) = event else {
+            continue;
+        };
+        let mut words = info.split_ascii_whitespace();
+        let Some(language) = words.next() else {
+            continue;
+        };
+
+        let mut attributes = String::new();
+        for option in words {
+            match option.split_once('=') {
+                None if option == "linenos" => attributes.push_str(r#" data-linenos="""#),
+                Some(("hl_lines", lines))
+                    if lines
+                        .chars()
+                        .all(|c| c.is_ascii_digit() || c == ',' || c == '-') =>
+                {
+                    attributes.push_str(&format!(r#" data-hl-lines="{lines}""#))
+                }
+                _ => {}
+            }
+        }
+        // Without options the renderer's own `language-*` class is all a block needs. The
+        // language is only written out by hand when it can't need escaping.
+        let plain = |c: char| c.is_ascii_alphanumeric() || "+#-_.".contains(c);
+        if attributes.is_empty() || !language.chars().all(plain) {
+            continue;
+        }
+
+        *event = Event::Html(CowStr::from(format!(
+            r#"<pre><code class="language-{language}"{attributes}>"#
+        )));
+    }
+}
+
+/// An id for a heading reading `text`: lowercase letters and digits, with anything else between
+/// words turned into a single `-`.
+pub(super) fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    match slug.trim_matches('-') {
+        "" => "section".to_string(),
+        slug => slug.to_string(),
+    }
+}
+
+/// `id`, or `id-1`, `id-2` and so on if it is already taken.
+pub(super) fn unique_id(id: &str, used: &mut HashSet<String>) -> String {
+    let mut candidate = id.to_string();
+    let mut suffix = 0;
+    while used.contains(&candidate) {
+        suffix += 1;
+        candidate = format!("{id}-{suffix}");
+    }
+
+    used.insert(candidate.clone());
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_gfm_extensions() {
+        let html = render(
+            "| a | b |\n|---|---|\n| 1 | 2 |\n\n\
+             - [x] done\n- [ ] todo\n\n\
+             ~~gone~~ and a note[^1]\n\n\
+             [^1]: The note.\n",
+        );
+
+        assert!(html.contains("<table>"));
+        assert!(html.contains(r#"<input disabled="" type="checkbox" checked=""/>"#));
+        assert!(html.contains("<del>gone</del>"));
+        assert!(html.contains(r#"class="footnote-definition""#));
+    }
+
+    #[test]
+    fn test_assigns_unique_heading_ids() {
+        let html =
+            render("# Hello, `World`!\n\n## Hello World\n\n## Custom {#hello-world-1}\n\n## ???\n");
+
+        assert!(html.contains(r#"<h1 id="hello-world">Hello, <code>World</code>!</h1>"#));
+        assert!(html.contains(r#"<h2 id="hello-world-2">Hello World</h2>"#));
+        assert!(html.contains(r#"<h2 id="hello-world-1">Custom</h2>"#));
+        assert!(html.contains(r#"<h2 id="section">???</h2>"#));
+    }
+
+    #[test]
+    fn test_annotates_code_blocks() {
+        let html = render("```rust linenos hl_lines=1,3-4\nfn main() {}\n```\n\n```sh\nls\n```\n");
+
+        assert!(html.contains(
+            r#"<pre><code class="language-rust" data-linenos="" data-hl-lines="1,3-4">fn main"#
+        ));
+        assert!(html.contains(r#"<pre><code class="language-sh">ls"#));
+    }
+}
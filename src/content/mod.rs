@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+use bytes::Bytes;
+use futures::stream::{BoxStream, StreamExt};
+use serde_json::Value;
+
+mod highlight;
+mod leaky;
+mod local;
+mod markdown;
+mod outline;
+mod sanitize;
+mod text;
+
+pub use highlight::{highlight_code_blocks, highlight_stylesheet};
+pub use leaky::LeakySource;
+pub use local::LocalSource;
+pub use outline::{anchor_headings, outline};
+pub use sanitize::{sanitize_html, text_only};
+pub use text::html_to_text;
+
+use crate::leaky::{BreakerState, LeakyClientError};
+
+/// Where the site's writing and images come from. Listings are returned as raw manifest entries in
+/// the `[name, [cid, {created_at, updated_at, metadata}]]` shape leaky serves, so the API handlers
+/// parse them the same way regardless of which backend produced them.
+#[async_trait]
+pub trait ContentSource {
+    /// List the manifest entries for every post.
+    async fn list_posts(&self) -> Result<Vec<Value>, ContentSourceError>;
+
+    /// Fetch a single post, rendered to HTML.
+    async fn get_post(&self, name: &str) -> Result<Bytes, ContentSourceError>;
+
+    /// List the manifest entries for every image.
+    async fn list_images(&self) -> Result<Vec<Value>, ContentSourceError>;
+
+    /// Fetch a single image.
+    async fn get_image(&self, name: &str) -> Result<Asset, ContentSourceError>;
+
+    /// Stream a single image rather than buffering it. Backends may honour `range`, the raw value
+    /// of a `Range` header, by returning only part of the body along with its `Content-Range`, or
+    /// ignore it and return the whole image. The default buffers via [`Self::get_image`].
+    async fn stream_image(
+        &self,
+        name: &str,
+        _range: Option<&str>,
+    ) -> Result<AssetStream, ContentSourceError> {
+        let asset = self.get_image(name).await?;
+
+        Ok(AssetStream {
+            content_type: asset.content_type,
+            content_length: Some(asset.bytes.len() as u64),
+            content_range: None,
+            body: futures::stream::once(async move { Ok(asset.bytes) }).boxed(),
+        })
+    }
+
+    /// Check whether the backend is currently able to serve content.
+    async fn check(&self) -> Result<(), ContentSourceError>;
+
+    /// The state of the circuit breaker in front of the backend, for backends that have one.
+    fn breaker_state(&self) -> Option<BreakerState> {
+        None
+    }
+}
+
+/// The bytes of a binary asset, along with the content type its backend claims it has, if any.
+#[derive(Clone, Debug)]
+pub struct Asset {
+    pub bytes: Bytes,
+    pub content_type: Option<String>,
+}
+
+/// A binary asset whose body is read as it is sent on.
+pub struct AssetStream {
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    /// Set when the backend honoured a range request and `body` holds only that range.
+    pub content_range: Option<String>,
+    pub body: BoxStream<'static, Result<Bytes, ContentSourceError>>,
+}
+
+pub type DynContentSource = Arc<dyn ContentSource + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ContentSourceError {
+    #[error(transparent)]
+    Leaky(#[from] LeakyClientError),
+    #[error("no such content")]
+    NotFound,
+    #[error("content source is unavailable")]
+    Unavailable,
+    #[error("failed to read local content: {0}")]
+    LocalReadFailed(#[from] std::io::Error),
+    #[error("invalid front matter in {name}: {reason}")]
+    InvalidFrontMatter { name: String, reason: String },
+}
+
+impl ContentSourceError {
+    /// Whether the content doesn't exist, as opposed to failing to be fetched.
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            ContentSourceError::NotFound | ContentSourceError::Leaky(LeakyClientError::NotFound)
+        )
+    }
+}
@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use bytes::Bytes;
+use lol_html::errors::RewritingError;
+use lol_html::html_content::{ContentType, Element, TextChunk};
+use lol_html::{element, text, RewriteStrSettings};
+use serde::Serialize;
+
+use super::markdown::{slugify, unique_id};
+use super::text::decode_entities;
+
+/// The headings a post's table of contents is made of.
+const OUTLINE_HEADINGS: &str = "h2, h3";
+
+/// A section of a post, as listed in its table of contents.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Heading {
+    /// 2 for an `h2`, 3 for an `h3`.
+    pub level: u8,
+    /// The `id` the heading is linked to by.
+    pub id: String,
+    pub text: String,
+}
+
+/// The `h2` and `h3` headings of a post's HTML, in order. Headings without an `id` are given the
+/// one [`anchor_headings`] would give them.
+pub fn outline(html: &str) -> Vec<Heading> {
+    match scan(html) {
+        Ok(headings) => headings,
+        Err(err) => {
+            tracing::warn!("failed to outline a post: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// Give every `h2` and `h3` in a post's HTML a stable `id`, derived from its text when it doesn't
+/// already have one, and an empty `heading-anchor` link to itself for the page to style. HTML
+/// that can't be rewritten is returned as it was.
+pub fn anchor_headings(html: Bytes) -> Bytes {
+    let Ok(source) = std::str::from_utf8(&html) else {
+        return html;
+    };
+
+    match scan(source).and_then(|headings| anchor(source, headings)) {
+        Ok(rewritten) => Bytes::from(rewritten),
+        Err(err) => {
+            tracing::warn!("failed to anchor headings: {err}");
+            html
+        }
+    }
+}
+
+/// A heading as it is read, before it's sure to have an id.
+#[derive(Debug)]
+struct ScannedHeading {
+    level: u8,
+    id: Option<String>,
+    raw_text: String,
+}
+
+/// Read the outline of `html`, settling the id of every heading that lacks one.
+fn scan(html: &str) -> Result<Vec<Heading>, RewritingError> {
+    let used: Rc<RefCell<HashSet<String>>> = Rc::default();
+    let scanned: Rc<RefCell<Vec<ScannedHeading>>> = Rc::default();
+    let in_heading = Rc::new(RefCell::new(false));
+
+    let on_id = {
+        let used = used.clone();
+        move |el: &mut Element| {
+            if let Some(id) = el.get_attribute("id") {
+                used.borrow_mut().insert(id);
+            }
+            Ok(())
+        }
+    };
+    let on_heading = {
+        let scanned = scanned.clone();
+        let in_heading = in_heading.clone();
+        move |el: &mut Element| {
+            let level = if el.tag_name() == "h2" { 2 } else { 3 };
+            scanned.borrow_mut().push(ScannedHeading {
+                level,
+                id: el.get_attribute("id").filter(|id| !id.is_empty()),
+                raw_text: String::new(),
+            });
+            *in_heading.borrow_mut() = true;
+
+            let in_heading = in_heading.clone();
+            if let Some(end_tag_handlers) = el.end_tag_handlers() {
+                end_tag_handlers.push(Box::new(move |_| {
+                    *in_heading.borrow_mut() = false;
+                    Ok(())
+                }));
+            }
+            Ok(())
+        }
+    };
+    let on_text = {
+        let scanned = scanned.clone();
+        move |chunk: &mut TextChunk| {
+            if *in_heading.borrow() {
+                if let Some(heading) = scanned.borrow_mut().last_mut() {
+                    heading.raw_text.push_str(chunk.as_str());
+                }
+            }
+            Ok(())
+        }
+    };
+
+    lol_html::rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![
+                element!("[id]", on_id),
+                element!(OUTLINE_HEADINGS, on_heading),
+                text!(OUTLINE_HEADINGS, on_text),
+            ],
+            ..RewriteStrSettings::default()
+        },
+    )?;
+
+    // Every id in the document is claimed before any is derived, so a heading never takes one
+    // that's already in use further down.
+    let mut used = used.take();
+    let headings = scanned
+        .take()
+        .into_iter()
+        .map(|heading| {
+            let text = decode_entities(&heading.raw_text)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            Heading {
+                level: heading.level,
+                id: heading
+                    .id
+                    .unwrap_or_else(|| unique_id(&slugify(&text), &mut used)),
+                text,
+            }
+        })
+        .collect();
+
+    Ok(headings)
+}
+
+/// Set the ids `headings` settled on, and append a self-link to each heading.
+fn anchor(html: &str, headings: Vec<Heading>) -> Result<String, RewritingError> {
+    let mut headings = headings.into_iter();
+    let on_heading = move |el: &mut Element| {
+        let Some(heading) = headings.next() else {
+            return Ok(());
+        };
+        if !el.has_attribute("id") {
+            el.set_attribute("id", &heading.id)?;
+        }
+
+        let href = format!("#{}", heading.id)
+            .replace('&', "&amp;")
+            .replace('"', "&quot;");
+        el.append(
+            &format!(
+                r#"<a class="heading-anchor" href="{href}" aria-label="Link to this section"></a>"#
+            ),
+            ContentType::Html,
+        );
+        Ok(())
+    };
+
+    lol_html::rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![element!(OUTLINE_HEADINGS, on_heading)],
+            ..RewriteStrSettings::default()
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POST: &str = concat!(
+        "<h1>Title</h1><h2>Hello <code>World</code> &amp; more</h2><p>Text</p>",
+        r#"<h3 id="custom">Deeper</h3><h2>Intro</h2><p id="intro">Taken</p>"#,
+    );
+
+    #[test]
+    fn test_outlines_headings() {
+        assert_eq!(
+            outline(POST),
+            vec![
+                Heading {
+                    level: 2,
+                    id: "hello-world-more".to_string(),
+                    text: "Hello World & more".to_string(),
+                },
+                Heading {
+                    level: 3,
+                    id: "custom".to_string(),
+                    text: "Deeper".to_string(),
+                },
+                Heading {
+                    level: 2,
+                    id: "intro-1".to_string(),
+                    text: "Intro".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_anchors_headings() {
+        let anchored = anchor_headings(Bytes::from(POST));
+        let anchored = std::str::from_utf8(&anchored).unwrap();
+
+        assert!(anchored.starts_with("<h1>Title</h1>"));
+        assert!(anchored.contains(concat!(
+            r#"<h2 id="hello-world-more">Hello <code>World</code> &amp; more"#,
+            r##"<a class="heading-anchor" href="#hello-world-more" aria-label="Link to this section"></a></h2>"##,
+        )));
+        assert!(anchored
+            .contains(r##"<h3 id="custom">Deeper<a class="heading-anchor" href="#custom""##));
+        // Anchored HTML outlines the same as it did before.
+        assert_eq!(outline(anchored), outline(POST));
+    }
+}
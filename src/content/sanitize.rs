@@ -0,0 +1,343 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use bytes::Bytes;
+use lol_html::errors::RewritingError;
+use lol_html::html_content::Element;
+use lol_html::{element, RewriteStrSettings};
+
+use super::text::{decode_entities, html_to_text};
+
+/// Elements a post may be made of. Anything else is unwrapped, keeping what's inside it.
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "input",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "samp",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "tr",
+    "u",
+    "ul",
+    "var",
+];
+
+/// Elements removed along with everything inside them, since their content is either script or
+/// not meant to be read. That includes every element whose content is parsed as raw text, which
+/// no handler sees into and which would turn back into live markup if the element was unwrapped.
+const DROPPED_ELEMENTS: &[&str] = &[
+    "applet",
+    "base",
+    "embed",
+    "frame",
+    "frameset",
+    "iframe",
+    "link",
+    "math",
+    "meta",
+    "noembed",
+    "noframes",
+    "noscript",
+    "object",
+    "plaintext",
+    "script",
+    "style",
+    "svg",
+    "template",
+    "textarea",
+    "title",
+    "xmp",
+];
+
+/// Attributes any allowed element may keep, on top of `data-*` ones.
+const GLOBAL_ATTRIBUTES: &[&str] = &["class", "dir", "id", "lang", "title"];
+
+/// Attributes holding a URL, which must be relative or use one of [`SAFE_SCHEMES`].
+const URL_ATTRIBUTES: &[&str] = &["cite", "href", "src"];
+
+const SAFE_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Run a post's HTML through an allowlist, so a compromised or broken upstream can't get script
+/// onto the site. Scripts, styles, frames and the like are dropped with their contents, unknown
+/// elements are unwrapped, and only known attributes are kept, with URLs limited to safe schemes.
+/// Links off the site get `rel="noopener noreferrer"` and images load lazily.
+///
+/// Whatever is removed is logged against `name`. HTML that can't be rewritten is reduced to its
+/// text rather than passed through.
+pub fn sanitize_html(name: &str, html: Bytes) -> Bytes {
+    let source = String::from_utf8_lossy(&html);
+    match sanitize(&source) {
+        Ok((sanitized, removals)) => {
+            if !removals.is_empty() {
+                let removed: Vec<String> = removals
+                    .into_iter()
+                    .map(|(what, count)| format!("{what} ({count})"))
+                    .collect();
+                tracing::warn!("sanitized {name}, removing {}", removed.join(", "));
+            }
+            Bytes::from(sanitized)
+        }
+        Err(err) => {
+            tracing::error!("failed to sanitize {name}, serving its text only: {err}");
+            text_only(&html)
+        }
+    }
+}
+
+/// The readable text of `html` as a single escaped paragraph, for when it can't be sanitized.
+pub fn text_only(html: &[u8]) -> Bytes {
+    let text = html_to_text(&String::from_utf8_lossy(html))
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    Bytes::from(format!("<p>{text}</p>"))
+}
+
+/// The sanitized HTML, and how many of each thing were removed from it.
+fn sanitize(html: &str) -> Result<(String, BTreeMap<String, usize>), RewritingError> {
+    let removals: Rc<RefCell<BTreeMap<String, usize>>> = Rc::default();
+    // How many dropped elements the rewriter is inside of. Their descendants go with them, so
+    // there's nothing more to check or report.
+    let dropping = Rc::new(RefCell::new(0usize));
+
+    let on_element = {
+        let removals = removals.clone();
+        move |el: &mut Element| {
+            if *dropping.borrow() > 0 {
+                return Ok(());
+            }
+            let removed = |what: String| *removals.borrow_mut().entry(what).or_default() += 1;
+            let tag = el.tag_name();
+
+            if DROPPED_ELEMENTS.contains(&tag.as_str()) {
+                el.remove();
+                removed(format!("<{tag}>"));
+                if let Some(end_tag_handlers) = el.end_tag_handlers() {
+                    *dropping.borrow_mut() += 1;
+                    let dropping = dropping.clone();
+                    end_tag_handlers.push(Box::new(move |_| {
+                        *dropping.borrow_mut() -= 1;
+                        Ok(())
+                    }));
+                }
+                return Ok(());
+            }
+            if !ALLOWED_ELEMENTS.contains(&tag.as_str()) {
+                el.remove_and_keep_content();
+                removed(format!("<{tag}> tag"));
+                return Ok(());
+            }
+            // Task list checkboxes are the only inputs a post has any use for.
+            if tag == "input" && !el.get_attribute("type").is_some_and(|t| t == "checkbox") {
+                el.remove();
+                removed("<input>".to_string());
+                return Ok(());
+            }
+
+            let attributes: Vec<(String, String)> = el
+                .attributes()
+                .iter()
+                .map(|attribute| (attribute.name(), attribute.value()))
+                .collect();
+            for (attribute, value) in attributes {
+                if !allows_attribute(&tag, &attribute) {
+                    el.remove_attribute(&attribute);
+                    if attribute.starts_with("on") {
+                        removed("event handler".to_string());
+                    } else {
+                        removed(format!("{attribute} attribute"));
+                    }
+                } else if URL_ATTRIBUTES.contains(&attribute.as_str()) && !is_safe_url(&value) {
+                    el.remove_attribute(&attribute);
+                    removed(format!("unsafe {attribute}"));
+                } else if attribute == "style" && !is_text_alignment(&value) {
+                    el.remove_attribute(&attribute);
+                    removed("style attribute".to_string());
+                }
+            }
+
+            match tag.as_str() {
+                "a" if el
+                    .get_attribute("href")
+                    .is_some_and(|href| is_external(&href)) =>
+                {
+                    el.set_attribute("rel", "noopener noreferrer")?;
+                }
+                "img" if !el.has_attribute("loading") => el.set_attribute("loading", "lazy")?,
+                "input" => el.set_attribute("disabled", "")?,
+                _ => {}
+            }
+            Ok(())
+        }
+    };
+
+    let sanitized = lol_html::rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![element!("*", on_element)],
+            ..RewriteStrSettings::default()
+        },
+    )?;
+
+    let removals = removals.take();
+    Ok((sanitized, removals))
+}
+
+fn allows_attribute(tag: &str, attribute: &str) -> bool {
+    GLOBAL_ATTRIBUTES.contains(&attribute)
+        || attribute.starts_with("data-")
+        || matches!(
+            (tag, attribute),
+            ("a", "href")
+                | ("img", "src" | "alt" | "width" | "height" | "loading")
+                | ("ol", "start" | "reversed")
+                | ("li", "value")
+                | ("td" | "th", "colspan" | "rowspan" | "align" | "style")
+                | ("input", "type" | "checked" | "disabled")
+                | ("blockquote" | "q" | "del" | "ins", "cite")
+                | ("del" | "ins" | "time", "datetime")
+                | ("details", "open")
+        )
+}
+
+/// Whether `url`, as written in an attribute, is relative or uses a safe scheme. Browsers ignore
+/// whitespace and control characters in a scheme and decode entities first, and so does this. An
+/// entity left undecoded could be hiding a `:`, so it isn't trusted either.
+fn is_safe_url(url: &str) -> bool {
+    let url: String = decode_entities(url)
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect();
+    let before_path = url.split(['/', '?', '#']).next().unwrap_or_default();
+    if before_path.contains('&') {
+        return false;
+    }
+
+    match before_path.split_once(':') {
+        Some((scheme, _)) => SAFE_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()),
+        None => true,
+    }
+}
+
+/// Whether `href` leads off the site.
+fn is_external(href: &str) -> bool {
+    let href = decode_entities(href).trim().to_ascii_lowercase();
+    href.starts_with("http:") || href.starts_with("https:") || href.starts_with("//")
+}
+
+/// The only inline style a post keeps: table cell alignment, as Markdown renderers write it.
+fn is_text_alignment(style: &str) -> bool {
+    matches!(
+        style.trim().trim_end_matches(';').replace(' ', "").as_str(),
+        "text-align:left" | "text-align:center" | "text-align:right"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitizes_html() {
+        let html = concat!(
+            r#"<p onclick="steal()">Hi <script>alert(1)</script><a href="java&#x09;script:x">bad</a> "#,
+            r#"<a href="https://example.com" target="_blank">out</a> <a href="/blog/x">in</a></p>"#,
+            r#"<svg><a href="/x">nested</a></svg><form><img src="/a.png" srcset="x"></form>"#,
+            r#"<table><tr><th style="text-align: center">h</th><td style="color: red">d</td></tr></table>"#,
+            r#"<input type="checkbox" checked=""><input type="text"><pre><code data-linenos="">x</code></pre>"#,
+        );
+        let (sanitized, removals) = sanitize(html).unwrap();
+
+        assert_eq!(
+            sanitized,
+            concat!(
+                r#"<p>Hi <a>bad</a> "#,
+                r#"<a href="https://example.com" rel="noopener noreferrer">out</a> <a href="/blog/x">in</a></p>"#,
+                r#"<img src="/a.png" loading="lazy">"#,
+                r#"<table><tr><th style="text-align: center">h</th><td>d</td></tr></table>"#,
+                r#"<input type="checkbox" checked="" disabled=""><pre><code data-linenos="">x</code></pre>"#,
+            )
+        );
+        assert_eq!(removals["<script>"], 1);
+        assert_eq!(removals["<svg>"], 1);
+        assert_eq!(removals["<form> tag"], 1);
+        assert_eq!(removals["event handler"], 1);
+        assert_eq!(removals["unsafe href"], 1);
+        assert!(!removals.contains_key("<a> tag"));
+
+        // Raw text elements hold markup no handler sees, so they can't be unwrapped.
+        for tag in [
+            "textarea",
+            "xmp",
+            "noembed",
+            "noframes",
+            "noscript",
+            "title",
+            "plaintext",
+        ] {
+            let html = format!("<p>a</p><{tag}><img src=x onerror=alert(1)></{tag}><p>b</p>");
+            let (sanitized, removals) = sanitize(&html).unwrap();
+            assert!(!sanitized.contains("<img"), "{tag}: {sanitized}");
+            assert!(sanitized.starts_with("<p>a</p>"), "{tag}: {sanitized}");
+            assert_eq!(removals[&format!("<{tag}>")], 1);
+        }
+    }
+
+    #[test]
+    fn test_checks_url_schemes() {
+        assert!(is_safe_url("https://example.com/a:b"));
+        assert!(is_safe_url("#section"));
+        assert!(is_safe_url("images/a.png?x=1:2"));
+        assert!(is_safe_url("mailto:me@example.com"));
+        assert!(!is_safe_url(" JavaScript:alert(1)"));
+        assert!(!is_safe_url("javascript&colon;alert(1)"));
+        assert!(!is_safe_url("data:text/html,<script>"));
+    }
+}
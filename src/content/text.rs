@@ -0,0 +1,162 @@
+/// Elements whose boundaries separate words, so `<p>a</p><p>b</p>` reads as `a b` rather than `ab`.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// Reduce rendered HTML to its readable text. Tags are dropped along with the contents of
+/// `<script>` and `<style>`, entities are decoded and runs of whitespace are collapsed into single
+/// spaces.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut skipping: Option<&str> = None;
+
+    loop {
+        // Scripts and styles may hold a `<` of their own, so skip straight to their closing tag.
+        let next_tag = match skipping {
+            Some(skipped) => rest.match_indices("</").map(|(i, _)| i).find(|&i| {
+                rest[i + 2..]
+                    .get(..skipped.len())
+                    .is_some_and(|name| name.eq_ignore_ascii_case(skipped))
+            }),
+            None => rest.find('<'),
+        };
+        let Some(start) = next_tag else {
+            break;
+        };
+        if skipping.is_none() {
+            push_decoded(&mut text, &rest[..start]);
+        }
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            // An unterminated tag, nothing after it is text.
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_ascii_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match skipping {
+            Some(skipped) if closing && name == skipped => skipping = None,
+            Some(_) => {}
+            None if !closing && name == "script" => skipping = Some("script"),
+            None if !closing && name == "style" => skipping = Some("style"),
+            None if BLOCK_ELEMENTS.contains(&name.as_str()) => text.push(' '),
+            None => {}
+        }
+    }
+    if skipping.is_none() {
+        push_decoded(&mut text, rest);
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Decode the entities in a run of HTML text, leaving everything else as written.
+pub(super) fn decode_entities(raw: &str) -> String {
+    let mut text = String::with_capacity(raw.len());
+    push_decoded(&mut text, raw);
+    text
+}
+
+fn push_decoded(text: &mut String, mut raw: &str) {
+    while let Some(amp) = raw.find('&') {
+        text.push_str(&raw[..amp]);
+        raw = &raw[amp..];
+
+        let decoded = raw
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&raw[1..end]).map(|c| (c, end + 1)));
+        match decoded {
+            Some((c, len)) => {
+                text.push(c);
+                raw = &raw[len..];
+            }
+            None => {
+                text.push('&');
+                raw = &raw[1..];
+            }
+        }
+    }
+    text.push_str(raw);
+}
+
+/// The character an entity stands for. Only the named entities a Markdown renderer emits are
+/// known, anything else is left as written.
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => entity.strip_prefix('#')?.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<h1>Hello</h1>\n<p>Some <em>te</em>xt &amp; a <a href=\"x\">link</a>&#33;</p>\
+                    <!-- a > comment --><script>let x = 1 < 2;</script><p>Tom &amp Jerry</p>";
+
+        assert_eq!(
+            html_to_text(html),
+            "Hello Some text & a link! Tom &amp Jerry"
+        );
+    }
+}
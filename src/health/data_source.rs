@@ -2,15 +2,27 @@
 
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use axum::async_trait;
-use axum::extract::FromRequestParts;
+use axum::extract::{FromRef, FromRequestParts};
 use http::request::Parts;
+use serde_json::Value;
+use tokio::sync::{watch, Mutex};
+
+use crate::app::AppState;
+use crate::content::DynContentSource;
+use crate::ssr::ShutdownState;
 
 #[async_trait]
 pub trait DataSource {
     /// Perform various checks on the system to ensure its healthy and ready to accept requests.
     async fn is_ready(&self) -> Result<(), DataSourceError>;
+
+    /// Details of the individual checks behind [`Self::is_ready`], reported alongside its result.
+    fn checks(&self) -> Option<Value> {
+        None
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -41,30 +53,130 @@ impl Deref for StateDataSource {
     }
 }
 
-struct ExampleSource;
+/// How long a probe result is reused before the content source is checked again, so frequent
+/// health checks don't turn into a steady stream of requests upstream.
+const PROBE_TTL: Duration = Duration::from_secs(5);
+
+/// How long a probe may take before the content source is considered unavailable.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Reports the service as not ready while the content source can't serve anything, or once we've
+/// been told to shut down, so load balancers route traffic to instances that can handle it.
+#[derive(Clone)]
+pub struct ReadinessProbe {
+    content_source: DynContentSource,
+    shutdown_rx: watch::Receiver<ShutdownState>,
+    last_probe: Arc<Mutex<Option<(Instant, bool)>>>,
+}
+
+impl ReadinessProbe {
+    pub fn new(
+        content_source: DynContentSource,
+        shutdown_rx: watch::Receiver<ShutdownState>,
+    ) -> Self {
+        Self {
+            content_source,
+            shutdown_rx,
+            last_probe: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    async fn content_source_ready(&self) -> bool {
+        // Holding the lock across the probe means concurrent health checks share one probe.
+        let mut last_probe = self.last_probe.lock().await;
+        if let Some((probed_at, ready)) = *last_probe {
+            if probed_at.elapsed() < PROBE_TTL {
+                return ready;
+            }
+        }
+
+        let ready = match tokio::time::timeout(PROBE_TIMEOUT, self.content_source.check()).await {
+            Ok(Ok(())) => true,
+            Ok(Err(err)) => {
+                tracing::warn!("content source is not ready: {err}");
+                false
+            }
+            Err(_) => {
+                tracing::warn!("content source readiness probe timed out");
+                false
+            }
+        };
+
+        *last_probe = Some((Instant::now(), ready));
+        ready
+    }
+}
 
 #[async_trait]
-impl DataSource for ExampleSource {
+impl DataSource for ReadinessProbe {
     async fn is_ready(&self) -> Result<(), DataSourceError> {
-        Ok(())
+        if *self.shutdown_rx.borrow() != ShutdownState::Running {
+            return Err(DataSourceError::ShuttingDown);
+        }
+
+        match self.content_source_ready().await {
+            true => Ok(()),
+            false => Err(DataSourceError::DependencyFailure),
+        }
+    }
+
+    fn checks(&self) -> Option<Value> {
+        let breaker = self.content_source.breaker_state()?;
+        Some(serde_json::json!({"leaky": {"breaker": breaker}}))
     }
 }
 
 #[async_trait]
 impl<S> FromRequestParts<S> for StateDataSource
 where
+    AppState: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = ();
 
-    async fn from_request_parts(_parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        Ok(StateDataSource(Arc::new(ExampleSource)))
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let readiness_probe = AppState::from_ref(state).readiness_probe;
+        Ok(StateDataSource(Arc::new(readiness_probe)))
     }
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
+    use bytes::Bytes;
+
     use super::*;
+    use crate::content::{Asset, ContentSource, ContentSourceError, LocalSource};
+    use crate::leaky::BreakerState;
+
+    /// A content source with nothing in it, behind a breaker stuck in the given state.
+    struct BreakerSource(BreakerState);
+
+    #[async_trait]
+    impl ContentSource for BreakerSource {
+        async fn list_posts(&self) -> Result<Vec<Value>, ContentSourceError> {
+            Ok(Vec::new())
+        }
+
+        async fn get_post(&self, _name: &str) -> Result<Bytes, ContentSourceError> {
+            Err(ContentSourceError::NotFound)
+        }
+
+        async fn list_images(&self) -> Result<Vec<Value>, ContentSourceError> {
+            Ok(Vec::new())
+        }
+
+        async fn get_image(&self, _name: &str) -> Result<Asset, ContentSourceError> {
+            Err(ContentSourceError::NotFound)
+        }
+
+        async fn check(&self) -> Result<(), ContentSourceError> {
+            Ok(())
+        }
+
+        fn breaker_state(&self) -> Option<BreakerState> {
+            Some(self.0)
+        }
+    }
 
     #[derive(Clone)]
     pub(crate) enum MockReadiness {
@@ -85,4 +197,51 @@ pub(crate) mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_readiness_probe_reports_shutdown() {
+        let content_source: DynContentSource = Arc::new(LocalSource::new(std::env::temp_dir()));
+        let (shutdown_tx, shutdown_rx) = watch::channel(ShutdownState::Running);
+        let probe = ReadinessProbe::new(content_source, shutdown_rx);
+
+        assert!(probe.is_ready().await.is_ok());
+
+        shutdown_tx.send(ShutdownState::Draining).unwrap();
+        assert!(matches!(
+            probe.is_ready().await,
+            Err(DataSourceError::ShuttingDown)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_readiness_probe_reports_unavailable_source() {
+        let missing = std::env::temp_dir().join("corpo-readiness-missing");
+        let content_source: DynContentSource = Arc::new(LocalSource::new(missing));
+        let (_shutdown_tx, shutdown_rx) = watch::channel(ShutdownState::Running);
+        let probe = ReadinessProbe::new(content_source, shutdown_rx);
+
+        assert!(matches!(
+            probe.is_ready().await,
+            Err(DataSourceError::DependencyFailure)
+        ));
+    }
+
+    #[test]
+    fn test_readiness_probe_reports_breaker_state() {
+        let (_shutdown_tx, shutdown_rx) = watch::channel(ShutdownState::Running);
+        let probe = |content_source: DynContentSource| {
+            ReadinessProbe::new(content_source, shutdown_rx.clone()).checks()
+        };
+
+        for (state, expected) in [
+            (BreakerState::Closed, "closed"),
+            (BreakerState::Open, "open"),
+            (BreakerState::HalfOpen, "half_open"),
+        ] {
+            let checks = probe(Arc::new(BreakerSource(state))).unwrap();
+            assert_eq!(checks["leaky"]["breaker"], expected);
+        }
+
+        assert!(probe(Arc::new(LocalSource::new(std::env::temp_dir()))).is_none());
+    }
 }
@@ -10,6 +10,8 @@ mod liveness;
 mod readiness;
 mod version;
 
+pub use data_source::ReadinessProbe;
+
 use crate::app::AppState;
 
 /// Healthcheck endpoints generally shouldn't contain anything other than headers which are counted
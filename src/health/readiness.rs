@@ -5,21 +5,22 @@ use axum::Json;
 use super::data_source::*;
 
 pub async fn handler(data_src: StateDataSource) -> Response {
-    match data_src.is_ready().await {
-        Ok(_) => {
-            let msg = serde_json::json!({"status": "ok"});
-            (StatusCode::OK, Json(msg)).into_response()
-        }
-        Err(DataSourceError::DependencyFailure) => {
-            let msg = serde_json::json!({"status": "failure", "message": "one or more dependencies aren't available"});
-            (StatusCode::SERVICE_UNAVAILABLE, Json(msg)).into_response()
-        }
-        Err(DataSourceError::ShuttingDown) => {
-            let msg =
-                serde_json::json!({"status": "failure", "message": "service is shutting down"});
-            (StatusCode::SERVICE_UNAVAILABLE, Json(msg)).into_response()
-        }
+    let (status, mut msg) = match data_src.is_ready().await {
+        Ok(_) => (StatusCode::OK, serde_json::json!({"status": "ok"})),
+        Err(DataSourceError::DependencyFailure) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            serde_json::json!({"status": "failure", "message": "one or more dependencies aren't available"}),
+        ),
+        Err(DataSourceError::ShuttingDown) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            serde_json::json!({"status": "failure", "message": "service is shutting down"}),
+        ),
+    };
+
+    if let Some(checks) = data_src.checks() {
+        msg["checks"] = checks;
     }
+    (status, Json(msg)).into_response()
 }
 
 #[cfg(test)]
@@ -0,0 +1,190 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { trial_in_flight: bool },
+}
+
+/// Stops sending requests to leaky once it has failed `failure_threshold` times in a row. While
+/// open every call fails fast; once the cooldown has elapsed a single trial request is let through
+/// and its outcome decides whether the breaker closes again or stays open for another cooldown.
+pub struct CircuitBreaker {
+    state: Mutex<State>,
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            state: Mutex::new(State::Closed { failures: 0 }),
+            failure_threshold,
+            cooldown,
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        match *self.state.lock().unwrap() {
+            State::Closed { .. } => BreakerState::Closed,
+            State::Open { until } if Instant::now() < until => BreakerState::Open,
+            State::Open { .. } | State::HalfOpen { .. } => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Asks for permission to make a request. When the breaker is open this returns how long the
+    /// caller should wait before trying again.
+    pub fn acquire(&self) -> Result<Permit<'_>, Duration> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        let trial = match *state {
+            State::Closed { .. } => false,
+            State::Open { until } if now < until => return Err(until - now),
+            State::Open { .. }
+            | State::HalfOpen {
+                trial_in_flight: false,
+            } => {
+                *state = State::HalfOpen {
+                    trial_in_flight: true,
+                };
+                true
+            }
+            State::HalfOpen {
+                trial_in_flight: true,
+            } => return Err(self.cooldown),
+        };
+
+        Ok(Permit {
+            breaker: self,
+            trial,
+            recorded: false,
+        })
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        if !matches!(*state, State::Closed { .. }) {
+            tracing::info!("leaky circuit breaker closed");
+        }
+        *state = State::Closed { failures: 0 };
+    }
+
+    fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        let trip = match *state {
+            State::Closed { failures } if failures + 1 < self.failure_threshold => {
+                *state = State::Closed {
+                    failures: failures + 1,
+                };
+                false
+            }
+            _ => true,
+        };
+
+        if trip {
+            tracing::warn!(cooldown = ?self.cooldown, "leaky circuit breaker opened");
+            *state = State::Open {
+                until: Instant::now() + self.cooldown,
+            };
+        }
+    }
+}
+
+/// Permission to make one request, whose outcome must be recorded through it.
+///
+/// A permit dropped without a recorded outcome, such as when the request's future is dropped
+/// because the client went away, counts as a failure if it was the half-open trial. Otherwise the
+/// trial would never finish and the breaker would stay shut for good.
+pub struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    trial: bool,
+    recorded: bool,
+}
+
+impl Permit<'_> {
+    pub fn record_success(mut self) {
+        self.recorded = true;
+        self.breaker.record_success();
+    }
+
+    pub fn record_failure(mut self) {
+        self.recorded = true;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.trial && !self.recorded {
+            tracing::warn!("leaky circuit breaker trial was abandoned");
+            self.breaker.record_failure();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_recovers() {
+        let breaker = CircuitBreaker::new(2, Duration::ZERO);
+
+        breaker.acquire().unwrap().record_failure();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        breaker.acquire().unwrap().record_failure();
+        assert_ne!(breaker.state(), BreakerState::Closed);
+
+        // With no cooldown the next caller becomes the half-open trial, and everyone else waits.
+        let trial = breaker.acquire().unwrap();
+        assert!(breaker.acquire().is_err());
+
+        trial.record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_recovers_from_abandoned_trial() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(50));
+        breaker.acquire().unwrap().record_failure();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // The trial's request is dropped mid-flight, as axum does when the client disconnects.
+        let request = async {
+            let _permit = breaker.acquire().unwrap();
+            std::future::pending::<()>().await;
+        };
+        assert!(tokio::time::timeout(Duration::from_millis(10), request)
+            .await
+            .is_err());
+
+        assert_eq!(breaker.state(), BreakerState::Open);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        breaker.acquire().unwrap().record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_fails_fast_while_open() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+
+        breaker.acquire().unwrap().record_failure();
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        let retry_after = breaker.acquire().err().unwrap();
+        assert!(retry_after <= Duration::from_secs(30));
+    }
+}
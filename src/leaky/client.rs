@@ -0,0 +1,313 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream::{BoxStream, StreamExt};
+use reqwest::header::{HeaderValue, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use reqwest::{Client, Response, StatusCode};
+use serde_json::Value;
+use url::Url;
+
+use super::breaker::{BreakerState, CircuitBreaker};
+use super::retry::RetryPolicy;
+use super::single_flight::SingleFlight;
+use crate::app::Config;
+
+/// Upper bound on the backoff between two attempts, however many retries are configured.
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// A shared handle on the leaky content service. The underlying [`reqwest::Client`] pools its
+/// connections, so this is cheap to clone and should be built once and kept in the app state rather
+/// than recreated per request. Concurrent requests for the same upstream URL share a single fetch,
+/// transient failures are retried, and a circuit breaker stops us waiting on a leaky that is down.
+#[derive(Clone)]
+pub struct LeakyClient {
+    client: Client,
+    base_url: Url,
+    in_flight: Arc<SingleFlight<Result<UpstreamBody, LeakyClientError>>>,
+    retry_policy: RetryPolicy,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl LeakyClient {
+    pub fn from_config(config: &Config) -> Result<Self, reqwest::Error> {
+        let client = Client::builder()
+            .connect_timeout(*config.leaky_connect_timeout())
+            .read_timeout(*config.leaky_read_timeout())
+            .user_agent(config.leaky_user_agent())
+            .build()?;
+
+        let retry_policy = RetryPolicy::new(
+            *config.leaky_max_retries(),
+            *config.leaky_retry_base_delay(),
+            RETRY_MAX_DELAY,
+        );
+        let breaker = CircuitBreaker::new(
+            *config.leaky_breaker_threshold(),
+            *config.leaky_breaker_cooldown(),
+        );
+
+        Ok(Self {
+            client,
+            base_url: config.leaky_url().clone(),
+            in_flight: Arc::new(SingleFlight::default()),
+            retry_policy,
+            breaker: Arc::new(breaker),
+        })
+    }
+
+    pub fn breaker_state(&self) -> BreakerState {
+        self.breaker.state()
+    }
+
+    /// Check that leaky is up by requesting its root. Any response short of a server error counts,
+    /// this only asks whether leaky is there to answer, not whether a particular piece of content
+    /// exists. Probes aren't retried and don't count towards the circuit breaker.
+    pub async fn probe(&self) -> Result<(), LeakyClientError> {
+        let response = self
+            .client
+            .get(self.base_url.clone())
+            .send()
+            .await
+            .map_err(LeakyClientError::from_send)?;
+
+        match response.status() {
+            status if status.is_server_error() => Err(LeakyClientError::BadStatus(status)),
+            _ => Ok(()),
+        }
+    }
+
+    /// List the raw manifest entries leaky holds under `/writing`.
+    pub async fn list_writing(&self) -> Result<Vec<Value>, LeakyClientError> {
+        self.get_json("/writing").await
+    }
+
+    /// Fetch a single piece of writing, rendered to HTML by leaky.
+    pub async fn get_writing_html(&self, name: &str) -> Result<Bytes, LeakyClientError> {
+        let body = self
+            .get_body(&format!("/writing/{}?html=true", name))
+            .await?;
+        Ok(body.bytes)
+    }
+
+    /// Fetch the Markdown source of a single piece of writing, to render ourselves.
+    pub async fn get_writing_markdown(&self, name: &str) -> Result<String, LeakyClientError> {
+        let body = self
+            .get_body(&format!("/writing/{}?html=false", name))
+            .await?;
+        Ok(String::from_utf8_lossy(&body.bytes).into_owned())
+    }
+
+    /// List the raw manifest entries leaky holds under `/visual`.
+    pub async fn list_visual(&self) -> Result<Vec<Value>, LeakyClientError> {
+        self.get_json("/visual").await
+    }
+
+    /// Fetch a single image, along with the content type leaky reported for it.
+    pub async fn get_visual(&self, name: &str) -> Result<UpstreamBody, LeakyClientError> {
+        self.get_body(&format!("/visual/{}", name)).await
+    }
+
+    /// Stream a single image without buffering it, forwarding `range` as a `Range` header so leaky
+    /// can answer with just the requested bytes. If leaky can't satisfy the range the whole image
+    /// is streamed instead, which is always a valid answer to a range request.
+    pub async fn stream_visual(
+        &self,
+        name: &str,
+        range: Option<&str>,
+    ) -> Result<UpstreamStream, LeakyClientError> {
+        let url = self.base_url.join(&format!("/visual/{}", name))?;
+
+        let permit = self
+            .breaker
+            .acquire()
+            .map_err(|retry_after| LeakyClientError::CircuitOpen { retry_after })?;
+
+        let result = self
+            .retry_policy
+            .run(|| async {
+                match Self::get_range(self.client.clone(), url.clone(), range).await {
+                    Err(LeakyClientError::BadStatus(StatusCode::RANGE_NOT_SATISFIABLE)) => {
+                        Self::get_range(self.client.clone(), url.clone(), None).await
+                    }
+                    result => result,
+                }
+            })
+            .await;
+
+        match &result {
+            Err(err) if err.is_transient() => permit.record_failure(),
+            _ => permit.record_success(),
+        }
+
+        let response = result?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let content_type = header(CONTENT_TYPE);
+        let content_range = match response.status() {
+            StatusCode::PARTIAL_CONTENT => header(CONTENT_RANGE),
+            _ => None,
+        };
+        let content_length = response.content_length();
+
+        let body = futures::stream::try_unfold(response, |mut response| async move {
+            let chunk = response
+                .chunk()
+                .await
+                .map_err(LeakyClientError::from_read)?;
+            Ok(chunk.map(|chunk| (chunk, response)))
+        })
+        .boxed();
+
+        Ok(UpstreamStream {
+            content_type,
+            content_length,
+            content_range,
+            body,
+        })
+    }
+
+    async fn get(client: Client, url: Url) -> Result<Response, LeakyClientError> {
+        Self::get_range(client, url, None).await
+    }
+
+    async fn get_range(
+        client: Client,
+        url: Url,
+        range: Option<&str>,
+    ) -> Result<Response, LeakyClientError> {
+        let mut request = client.get(url);
+        if let Some(range) = range {
+            request = request.header(RANGE, range);
+        }
+
+        let response = request.send().await.map_err(LeakyClientError::from_send)?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Err(LeakyClientError::NotFound),
+            status if !status.is_success() => Err(LeakyClientError::BadStatus(status)),
+            _ => Ok(response),
+        }
+    }
+
+    async fn get_body(&self, path: &str) -> Result<UpstreamBody, LeakyClientError> {
+        let url = self.base_url.join(path)?;
+        let key = url.to_string();
+        let client = self.client.clone();
+
+        let retry_policy = self.retry_policy;
+        let breaker = self.breaker.clone();
+
+        self.in_flight
+            .run(&key, || async move {
+                let permit = breaker
+                    .acquire()
+                    .map_err(|retry_after| LeakyClientError::CircuitOpen { retry_after })?;
+
+                let result = retry_policy
+                    .run(|| async {
+                        let response = Self::get(client.clone(), url.clone()).await?;
+                        let content_type = response
+                            .headers()
+                            .get(CONTENT_TYPE)
+                            .and_then(|value| value.to_str().ok())
+                            .map(str::to_string);
+                        let bytes = response
+                            .bytes()
+                            .await
+                            .map_err(LeakyClientError::from_read)?;
+
+                        Ok(UpstreamBody {
+                            bytes,
+                            content_type,
+                        })
+                    })
+                    .await;
+
+                match &result {
+                    Err(err) if err.is_transient() => permit.record_failure(),
+                    _ => permit.record_success(),
+                }
+
+                result
+            })
+            .await
+    }
+
+    async fn get_json(&self, path: &str) -> Result<Vec<Value>, LeakyClientError> {
+        let body = self.get_body(path).await?;
+        serde_json::from_slice(&body.bytes)
+            .map_err(|err| LeakyClientError::UndecodableBody(Arc::new(err)))
+    }
+}
+
+/// A response body from leaky, along with the `Content-Type` it was served with.
+#[derive(Clone, Debug)]
+pub struct UpstreamBody {
+    pub bytes: Bytes,
+    pub content_type: Option<String>,
+}
+
+/// A response body from leaky that is read as it arrives rather than buffered.
+pub struct UpstreamStream {
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    /// The `Content-Range` leaky answered with, if it sent back only part of the body.
+    pub content_range: Option<String>,
+    pub body: BoxStream<'static, Result<Bytes, LeakyClientError>>,
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum LeakyClientError {
+    #[error("failed to construct url: {0}")]
+    UrlJoinFailed(#[from] url::ParseError),
+    #[error("leaky is unreachable: {0}")]
+    Unreachable(Arc<reqwest::Error>),
+    #[error("request to leaky timed out: {0}")]
+    TimedOut(Arc<reqwest::Error>),
+    #[error("leaky has no such content")]
+    NotFound,
+    #[error("leaky responded with {0}")]
+    BadStatus(StatusCode),
+    #[error("failed to read leaky response: {0}")]
+    ResponseReadFailed(Arc<reqwest::Error>),
+    #[error("failed to decode leaky response: {0}")]
+    UndecodableBody(Arc<serde_json::Error>),
+    #[error("leaky circuit breaker is open, retry after {retry_after:?}")]
+    CircuitOpen { retry_after: Duration },
+}
+
+impl LeakyClientError {
+    /// Whether the failure might not happen again if the same request is retried.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Unreachable(_) | Self::TimedOut(_) | Self::ResponseReadFailed(_) => true,
+            Self::BadStatus(status) => status.is_server_error(),
+            Self::UrlJoinFailed(_)
+            | Self::NotFound
+            | Self::UndecodableBody(_)
+            | Self::CircuitOpen { .. } => false,
+        }
+    }
+
+    fn from_send(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::TimedOut(Arc::new(err))
+        } else {
+            Self::Unreachable(Arc::new(err))
+        }
+    }
+
+    fn from_read(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::TimedOut(Arc::new(err))
+        } else {
+            Self::ResponseReadFailed(Arc::new(err))
+        }
+    }
+}
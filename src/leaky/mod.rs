@@ -0,0 +1,7 @@
+mod breaker;
+mod client;
+mod retry;
+mod single_flight;
+
+pub use breaker::BreakerState;
+pub use client::{LeakyClient, LeakyClientError};
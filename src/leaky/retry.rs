@@ -0,0 +1,98 @@
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+
+use super::LeakyClientError;
+
+/// Retries idempotent requests that failed for transient reasons, sleeping a random duration
+/// between zero and an exponentially growing ceiling before each new attempt ("full jitter").
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay,
+            max_delay,
+        }
+    }
+
+    pub async fn run<F, Fut, T>(&self, mut f: F) -> Result<T, LeakyClientError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, LeakyClientError>>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            match f().await {
+                Err(err) if err.is_transient() && attempt < self.max_retries => {
+                    let delay = self.delay(attempt);
+                    tracing::debug!(attempt, ?delay, "retrying leaky request: {err}");
+
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+
+        ceiling.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(1), Duration::from_millis(4));
+        let attempts = AtomicU32::new(0);
+
+        let result = policy
+            .run(|| async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(LeakyClientError::BadStatus(
+                        reqwest::StatusCode::BAD_GATEWAY,
+                    )),
+                    _ => Ok(()),
+                }
+            })
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_permanent_failures() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(1), Duration::from_millis(4));
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), _> = policy
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(LeakyClientError::NotFound)
+            })
+            .await;
+
+        assert!(matches!(result, Err(LeakyClientError::NotFound)));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
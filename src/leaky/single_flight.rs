@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use futures::future::{BoxFuture, FutureExt, Shared};
+
+/// Coalesces concurrent calls for the same key into a single in-flight future. Callers that arrive
+/// while a fetch is running wait on it and receive a clone of its output instead of issuing their
+/// own request.
+pub struct SingleFlight<T> {
+    in_flight: Mutex<HashMap<String, Shared<BoxFuture<'static, T>>>>,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T> SingleFlight<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    pub async fn run<F, Fut>(&self, key: &str, f: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        let flight = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+
+            match in_flight.get(key) {
+                Some(flight) => {
+                    tracing::debug!(key, "joining in-flight request");
+                    flight.clone()
+                }
+                None => {
+                    let flight = f().boxed().shared();
+                    in_flight.insert(key.to_string(), flight.clone());
+                    flight
+                }
+            }
+        };
+
+        let output = flight.clone().await;
+
+        // Only the first caller to finish clears the entry, and only if a newer flight hasn't
+        // already replaced it.
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(key)
+            .is_some_and(|current| current.ptr_eq(&flight))
+        {
+            in_flight.remove(key);
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_coalesces_concurrent_calls() {
+        let single_flight = Arc::new(SingleFlight::<usize>::default());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let tasks = (0..8).map(|_| {
+            let single_flight = single_flight.clone();
+            let calls = calls.clone();
+
+            async move {
+                single_flight
+                    .run("/writing/post", || async move {
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        calls.fetch_add(1, Ordering::SeqCst) + 1
+                    })
+                    .await
+            }
+        });
+
+        let outputs = futures::future::join_all(tasks).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(outputs.iter().all(|output| *output == 1));
+        assert!(single_flight.in_flight.lock().unwrap().is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+mod slug;
 #[allow(dead_code)]
 mod version;
 mod web;
@@ -7,9 +8,21 @@ mod api;
 #[cfg(feature = "ssr")]
 pub mod app;
 #[cfg(feature = "ssr")]
+mod cache;
+#[cfg(feature = "ssr")]
+mod content;
+#[cfg(feature = "ssr")]
 mod health;
 #[cfg(feature = "ssr")]
+mod leaky;
+#[cfg(feature = "ssr")]
+pub mod preview;
+#[cfg(feature = "ssr")]
+mod search;
+#[cfg(feature = "ssr")]
 mod server;
+#[cfg(feature = "ssr")]
+mod stats;
 
 #[cfg(feature = "ssr")]
 pub mod ssr {
@@ -21,11 +34,22 @@ pub mod ssr {
 
     const REQUEST_GRACE_PERIOD: Duration = Duration::from_secs(10);
 
-    pub fn graceful_shutdown_blocker() -> (JoinHandle<()>, watch::Receiver<()>) {
+    /// How far along shutting down the service is.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ShutdownState {
+        Running,
+        /// A SIGTERM has been received. We keep serving through the grace period but report
+        /// ourselves as not ready, so load balancers can drain traffic away from us.
+        Draining,
+        /// Services should stop now.
+        Stopping,
+    }
+
+    pub fn graceful_shutdown_blocker() -> (JoinHandle<()>, watch::Receiver<ShutdownState>) {
         let mut sigint = signal(SignalKind::interrupt()).unwrap();
         let mut sigterm = signal(SignalKind::terminate()).unwrap();
 
-        let (tx, rx) = tokio::sync::watch::channel(());
+        let (tx, rx) = tokio::sync::watch::channel(ShutdownState::Running);
 
         let handle = tokio::spawn(async move {
             tokio::select! {
@@ -33,6 +57,7 @@ pub mod ssr {
                     tracing::debug!("gracefully exiting immediately on SIGINT");
                 }
                 _ = sigterm.recv() => {
+                    let _ = tx.send(ShutdownState::Draining);
                     tokio::time::sleep(REQUEST_GRACE_PERIOD).await;
                     tracing::debug!("initiaing graceful shutdown with delay on SIGTERM");
                 }
@@ -40,7 +65,7 @@ pub mod ssr {
 
             // Time to start signaling any services that care about gracefully shutting down that the
             // time is at hand.
-            let _ = tx.send(());
+            let _ = tx.send(ShutdownState::Stopping);
         });
 
         (handle, rx)
@@ -49,7 +74,7 @@ pub mod ssr {
     pub async fn server(
         log_level: tracing::Level,
         state: crate::app::AppState,
-        shutdown_rx: watch::Receiver<()>,
+        shutdown_rx: watch::Receiver<ShutdownState>,
     ) -> JoinHandle<()> {
         tokio::spawn(async move {
             match crate::server::run(log_level, state, shutdown_rx).await {
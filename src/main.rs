@@ -13,6 +13,34 @@ async fn main() {
 
     const FINAL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
+    const USAGE: &str = "usage: corpo [preview <name>]";
+
+    // `corpo preview <name>` prints a link to preview an unpublished post instead of serving
+    let mut args = pico_args::Arguments::from_env();
+    let preview_name = match args.subcommand() {
+        Ok(None) => None,
+        Ok(Some(command)) if command == "preview" => match args.free_from_str::<String>() {
+            Ok(name) => Some(name),
+            Err(e) => {
+                eprintln!("Error parsing arguments: {}\n{USAGE}", e);
+                std::process::exit(1);
+            }
+        },
+        Ok(Some(command)) => {
+            eprintln!("Unknown command {command:?}\n{USAGE}");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error parsing arguments: {}\n{USAGE}", e);
+            std::process::exit(1);
+        }
+    };
+    let extra = args.finish();
+    if !extra.is_empty() {
+        eprintln!("Unexpected arguments {extra:?}\n{USAGE}");
+        std::process::exit(1);
+    }
+
     // Get the configuration from the environment
     let config = match Config::from_env() {
         Ok(config) => config,
@@ -22,6 +50,21 @@ async fn main() {
         }
     };
 
+    if let Some(name) = preview_name {
+        let Some(key) = config.preview_key() else {
+            eprintln!("Error creating preview link: PREVIEW_SECRET is not set");
+            std::process::exit(2);
+        };
+        match corpo::preview::preview_url(config.site_url(), key, &name, *config.preview_ttl()) {
+            Ok(url) => println!("{url}"),
+            Err(e) => {
+                eprintln!("Error creating preview link for {name:?}: {}", e);
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
     // Set up logging
     // TODO: conditional text decoration depending on the environment
     let (non_blocking_writer, _guard) = tracing_appender::non_blocking(std::io::stdout());
@@ -39,8 +82,10 @@ async fn main() {
     corpo::ssr::register_panic_logger();
     corpo::ssr::report_version();
 
+    let (graceful_waiter, shutdown_rx) = corpo::ssr::graceful_shutdown_blocker();
+
     // Create the app state
-    let state = match AppState::from_config(&config).await {
+    let state = match AppState::from_config(&config, shutdown_rx.clone()).await {
         Ok(state) => state,
         Err(e) => {
             eprintln!("Error creating app state: {}", e);
@@ -48,7 +93,6 @@ async fn main() {
         }
     };
 
-    let (graceful_waiter, shutdown_rx) = corpo::ssr::graceful_shutdown_blocker();
     let mut handles = Vec::new();
 
     let server = corpo::ssr::server(*config.log_level(), state, shutdown_rx).await;
@@ -0,0 +1,155 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::slug::{Slug, SlugError};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs and checks the tokens that let a post be read before it's published.
+///
+/// A token reads `<expires>.<signature>`: the Unix time it stops working at, and a hex encoded
+/// HMAC-SHA256 of the post's name and that time, so it can't be moved to another post or have
+/// its life extended.
+#[derive(Clone)]
+pub struct PreviewKey {
+    secret: Arc<[u8]>,
+}
+
+impl PreviewKey {
+    pub fn new(secret: &str) -> Self {
+        Self {
+            secret: secret.as_bytes().into(),
+        }
+    }
+
+    /// A token previewing the post called `name` until `expires_at`.
+    pub fn sign(&self, name: &str, expires_at: OffsetDateTime) -> String {
+        let expires = expires_at.unix_timestamp();
+        let signature = self.mac(name, expires).finalize().into_bytes();
+        format!("{expires}.{signature:x}")
+    }
+
+    /// Check that `token` previews the post called `name` and hasn't expired by `now`.
+    pub fn verify(&self, name: &str, token: &str, now: OffsetDateTime) -> Result<(), PreviewError> {
+        let (expires, signature) = token.split_once('.').ok_or(PreviewError::Malformed)?;
+        let expires: i64 = expires.parse().map_err(|_| PreviewError::Malformed)?;
+        let signature = decode_hex(signature).ok_or(PreviewError::Malformed)?;
+
+        self.mac(name, expires)
+            .verify_slice(&signature)
+            .map_err(|_| PreviewError::BadSignature)?;
+        if expires <= now.unix_timestamp() {
+            return Err(PreviewError::Expired);
+        }
+        Ok(())
+    }
+
+    fn mac(&self, name: &str, expires: i64) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC takes keys of any length");
+        mac.update(name.as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+}
+
+impl fmt::Debug for PreviewKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreviewKey").finish_non_exhaustive()
+    }
+}
+
+/// The address of a preview of the post called `name` on the site at `site_url`, working for
+/// `ttl` from now.
+///
+/// `name` is signed in its canonical form, the one the post is served under, and is rejected if
+/// it isn't a valid post name.
+pub fn preview_url(
+    site_url: &Url,
+    key: &PreviewKey,
+    name: &str,
+    ttl: Duration,
+) -> Result<Url, SlugError> {
+    let name = Slug::canonicalize(name)?;
+    let token = key.sign(name.as_str(), OffsetDateTime::now_utc() + ttl);
+    let mut url = site_url.clone();
+    url.set_path(&format!("/blog/{name}"));
+    url.query_pairs_mut().clear().append_pair("preview", &token);
+    Ok(url)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreviewError {
+    #[error("malformed preview token")]
+    Malformed,
+    #[error("preview token doesn't match the post")]
+    BadSignature,
+    #[error("preview token has expired")]
+    Expired,
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_verifies_preview_tokens() {
+        let key = PreviewKey::new("secret");
+        let now = OffsetDateTime::now_utc();
+        let token = key.sign("hello-world", now + Duration::hours(1));
+
+        assert!(key.verify("hello-world", &token, now).is_ok());
+        assert!(matches!(
+            key.verify("hello-world", &token, now + Duration::hours(2)),
+            Err(PreviewError::Expired)
+        ));
+        assert!(matches!(
+            key.verify("other-post", &token, now),
+            Err(PreviewError::BadSignature)
+        ));
+        assert!(matches!(
+            PreviewKey::new("other").verify("hello-world", &token, now),
+            Err(PreviewError::BadSignature)
+        ));
+        assert!(matches!(
+            key.verify("hello-world", "not-a-token", now),
+            Err(PreviewError::Malformed)
+        ));
+    }
+
+    #[test]
+    fn test_builds_preview_urls() {
+        let key = PreviewKey::new("secret");
+        let site_url = Url::parse("https://example.com").unwrap();
+        let ttl = std::time::Duration::from_secs(60);
+
+        let url = preview_url(&site_url, &key, "Hello-World/", ttl).unwrap();
+        assert_eq!(url.path(), "/blog/hello-world");
+        let (_, token) = url.query_pairs().next().unwrap();
+        assert!(key
+            .verify("hello-world", &token, OffsetDateTime::now_utc())
+            .is_ok());
+
+        assert!(preview_url(&site_url, &key, "../admin", ttl).is_err());
+        assert!(preview_url(&site_url, &key, "a?b", ttl).is_err());
+    }
+}
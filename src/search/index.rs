@@ -0,0 +1,480 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::time::Instant;
+
+use serde::Serialize;
+use time::OffsetDateTime;
+
+/// How much a match in each field counts for. Titles and descriptions are short and written to
+/// say what a post is about, so a word there says more than the same word in the body.
+const TITLE_WEIGHT: f32 = 3.0;
+const DESCRIPTION_WEIGHT: f32 = 2.0;
+const BODY_WEIGHT: f32 = 1.0;
+
+/// BM25's term frequency saturation and length normalization.
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// How much each tag two posts share adds to how related they are, on top of the cosine
+/// similarity of their text.
+const SHARED_TAG_WEIGHT: f32 = 0.2;
+
+/// How many words a snippet holds, and how many of them come before the first match.
+const SNIPPET_WORDS: usize = 30;
+const SNIPPET_LEAD: usize = 8;
+
+/// A post as the listing describes it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Document {
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub updated_at: OffsetDateTime,
+}
+
+/// A post along with its body text, which is `None` when it couldn't be fetched.
+#[derive(Clone, Debug)]
+pub(super) struct Indexed {
+    pub(super) document: Document,
+    pub(super) body: Option<String>,
+    /// Why the body is missing, if it failed to fetch.
+    pub(super) failure: Option<FetchFailure>,
+}
+
+/// A body that failed to fetch at a post's current revision, and when it's worth trying again.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct FetchFailure {
+    pub(super) attempts: u32,
+    pub(super) retry_at: Instant,
+}
+
+/// A post matching a query, with a snippet of its text where the query terms are wrapped in
+/// `<mark>`. Everything else in the snippet is escaped, so it can be used as HTML as is.
+#[derive(Clone, Debug, Serialize)]
+pub struct Hit {
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub snippet: String,
+    pub score: f32,
+}
+
+/// A post sharing tags or vocabulary with another.
+#[derive(Clone, Debug, Serialize)]
+pub struct Related {
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub score: f32,
+}
+
+/// An inverted index over every post's title, description and body.
+#[derive(Debug, Default)]
+pub struct Index {
+    documents: Vec<Indexed>,
+    /// For each term, the documents it appears in and its weighted frequency in each.
+    postings: HashMap<String, Vec<(usize, f32)>>,
+    /// Each document's TF-IDF weight for every term in it, and the length of that vector.
+    vectors: Vec<(HashMap<String, f32>, f32)>,
+    /// The weighted number of terms in each document.
+    lengths: Vec<f32>,
+    average_length: f32,
+}
+
+impl Index {
+    pub(super) fn build(documents: Vec<Indexed>) -> Self {
+        let mut postings: HashMap<String, Vec<(usize, f32)>> = HashMap::new();
+        let mut lengths = Vec::with_capacity(documents.len());
+        let mut term_frequencies = Vec::with_capacity(documents.len());
+
+        for (id, indexed) in documents.iter().enumerate() {
+            let fields = [
+                (indexed.document.title.as_str(), TITLE_WEIGHT),
+                (indexed.document.description.as_str(), DESCRIPTION_WEIGHT),
+                (indexed.body.as_deref().unwrap_or_default(), BODY_WEIGHT),
+            ];
+
+            let mut frequencies: HashMap<String, f32> = HashMap::new();
+            let mut length = 0.0;
+            for (text, weight) in fields {
+                for (_, term) in tokenize(text) {
+                    *frequencies.entry(term).or_default() += weight;
+                    length += weight;
+                }
+            }
+
+            for (term, &frequency) in &frequencies {
+                postings
+                    .entry(term.clone())
+                    .or_default()
+                    .push((id, frequency));
+            }
+            term_frequencies.push(frequencies);
+            lengths.push(length);
+        }
+
+        let document_count = documents.len() as f32;
+        let vectors = term_frequencies
+            .into_iter()
+            .map(|frequencies| {
+                let weights: HashMap<String, f32> = frequencies
+                    .into_iter()
+                    .map(|(term, frequency)| {
+                        let idf = (document_count / postings[&term].len() as f32).ln();
+                        (term, frequency * idf)
+                    })
+                    .collect();
+                let norm = weights
+                    .values()
+                    .map(|weight| weight * weight)
+                    .sum::<f32>()
+                    .sqrt();
+                (weights, norm)
+            })
+            .collect();
+
+        let average_length = match lengths.len() {
+            0 => 0.0,
+            count => lengths.iter().sum::<f32>() / count as f32,
+        };
+
+        Self {
+            documents,
+            postings,
+            vectors,
+            lengths,
+            average_length,
+        }
+    }
+
+    /// Whether this index was built from exactly `documents`, with every body either fetched or
+    /// not due to be tried again until after `now`.
+    pub(super) fn is_current(&self, documents: &[Document], now: Instant) -> bool {
+        self.documents.len() == documents.len()
+            && self
+                .documents
+                .iter()
+                .zip(documents)
+                .all(|(indexed, document)| {
+                    indexed.document == *document
+                        && (indexed.body.is_some()
+                            || indexed
+                                .failure
+                                .is_some_and(|failure| now < failure.retry_at))
+                })
+    }
+
+    /// What this index holds for `document` at the same revision, if anything.
+    pub(super) fn revision(&self, document: &Document) -> Option<&Indexed> {
+        self.documents.iter().find(|indexed| {
+            indexed.document.name == document.name
+                && indexed.document.updated_at == document.updated_at
+        })
+    }
+
+    /// This index with `document` added, in place of any post of the same name, for ranking a post
+    /// that isn't listed yet against those that are.
+    pub fn including(&self, document: Document, body: String) -> Self {
+        let mut documents: Vec<Indexed> = self
+            .documents
+            .iter()
+            .filter(|indexed| indexed.document.name != document.name)
+            .cloned()
+            .collect();
+        documents.push(Indexed {
+            document,
+            body: Some(body),
+            failure: None,
+        });
+        Self::build(documents)
+    }
+
+    /// The posts containing every term in `query`, best match first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Hit> {
+        let mut terms: Vec<String> = Vec::new();
+        for (_, term) in tokenize(query) {
+            if !terms.contains(&term) {
+                terms.push(term);
+            }
+        }
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let document_count = self.documents.len() as f32;
+        let mut scores: HashMap<usize, (usize, f32)> = HashMap::new();
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                return Vec::new();
+            };
+
+            let matching = postings.len() as f32;
+            let idf = (1.0 + (document_count - matching + 0.5) / (matching + 0.5)).ln();
+            for &(id, frequency) in postings {
+                let normalization = 1.0 - B + B * self.lengths[id] / self.average_length;
+                let score = idf * frequency * (K1 + 1.0) / (frequency + K1 * normalization);
+
+                let (matched, total) = scores.entry(id).or_default();
+                *matched += 1;
+                *total += score;
+            }
+        }
+
+        let mut ranked: Vec<(usize, f32)> = scores
+            .into_iter()
+            .filter(|(_, (matched, _))| *matched == terms.len())
+            .map(|(id, (_, score))| (id, score))
+            .collect();
+        ranked.sort_by(|(a_id, a), (b_id, b)| b.total_cmp(a).then_with(|| a_id.cmp(b_id)));
+        ranked.truncate(limit);
+
+        let terms: HashSet<String> = terms.into_iter().collect();
+        ranked
+            .into_iter()
+            .map(|(id, score)| {
+                let indexed = &self.documents[id];
+                let body = indexed.body.as_deref().unwrap_or_default();
+                let snippet = snippet(body, &terms)
+                    .or_else(|| snippet(&indexed.document.description, &terms))
+                    .unwrap_or_else(|| {
+                        highlight(body, &tokenize(body).collect::<Vec<_>>(), 0, &terms)
+                    });
+
+                Hit {
+                    name: indexed.document.name.clone(),
+                    title: indexed.document.title.clone(),
+                    description: indexed.document.description.clone(),
+                    snippet,
+                    score,
+                }
+            })
+            .collect()
+    }
+
+    /// The posts most like the one called `name`, by the tags they share and the cosine
+    /// similarity of their TF-IDF weighted text. Posts with nothing in common are left out, and
+    /// `None` means there's no post called `name`.
+    pub fn related(&self, name: &str, limit: usize) -> Option<Vec<Related>> {
+        let target = self
+            .documents
+            .iter()
+            .position(|indexed| indexed.document.name == name)?;
+        let (target_weights, target_norm) = &self.vectors[target];
+        let target_tags = &self.documents[target].document.tags;
+
+        let mut ranked: Vec<(usize, f32)> = self
+            .vectors
+            .iter()
+            .enumerate()
+            .filter(|&(id, _)| id != target)
+            .map(|(id, (weights, norm))| {
+                let similarity = if *target_norm > 0.0 && *norm > 0.0 {
+                    let (smaller, larger) = if weights.len() < target_weights.len() {
+                        (weights, target_weights)
+                    } else {
+                        (target_weights, weights)
+                    };
+                    let dot: f32 = smaller
+                        .iter()
+                        .filter_map(|(term, weight)| Some(weight * larger.get(term)?))
+                        .sum();
+                    dot / (target_norm * norm)
+                } else {
+                    0.0
+                };
+                let shared_tags = self.documents[id]
+                    .document
+                    .tags
+                    .iter()
+                    .filter(|tag| target_tags.contains(tag))
+                    .count();
+
+                (id, similarity + SHARED_TAG_WEIGHT * shared_tags as f32)
+            })
+            .filter(|&(_, score)| score > 0.0)
+            .collect();
+        ranked.sort_by(|(a_id, a), (b_id, b)| b.total_cmp(a).then_with(|| a_id.cmp(b_id)));
+        ranked.truncate(limit);
+
+        let related = ranked
+            .into_iter()
+            .map(|(id, score)| {
+                let document = &self.documents[id].document;
+                Related {
+                    name: document.name.clone(),
+                    title: document.title.clone(),
+                    description: document.description.clone(),
+                    tags: document.tags.clone(),
+                    score,
+                }
+            })
+            .collect();
+        Some(related)
+    }
+}
+
+/// The words in `text`, as their position in it and the lowercased term they index under.
+fn tokenize(text: &str) -> impl Iterator<Item = (Range<usize>, String)> + '_ {
+    let mut chars = text.char_indices().peekable();
+
+    std::iter::from_fn(move || {
+        let (start, _) = chars.find(|(_, c)| c.is_alphanumeric())?;
+        let mut end = text.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if !c.is_alphanumeric() {
+                end = i;
+                break;
+            }
+            chars.next();
+        }
+
+        Some((start..end, text[start..end].to_lowercase()))
+    })
+}
+
+/// A window of `text` around the first of `terms` in it, if there is one.
+fn snippet(text: &str, terms: &HashSet<String>) -> Option<String> {
+    let words: Vec<_> = tokenize(text).collect();
+    let first = words.iter().position(|(_, term)| terms.contains(term))?;
+
+    Some(highlight(
+        text,
+        &words,
+        first.saturating_sub(SNIPPET_LEAD),
+        terms,
+    ))
+}
+
+/// Escape the words of `text` from the `start`th on, marking any of `terms`.
+fn highlight(
+    text: &str,
+    words: &[(Range<usize>, String)],
+    start: usize,
+    terms: &HashSet<String>,
+) -> String {
+    let end = (start + SNIPPET_WORDS).min(words.len());
+    let Some((first, _)) = words.get(start) else {
+        return String::new();
+    };
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let mut cursor = first.start;
+    for (span, term) in &words[start..end] {
+        escape_into(&mut snippet, &text[cursor..span.start]);
+        if terms.contains(term) {
+            snippet.push_str("<mark>");
+            escape_into(&mut snippet, &text[span.clone()]);
+            snippet.push_str("</mark>");
+        } else {
+            escape_into(&mut snippet, &text[span.clone()]);
+        }
+        cursor = span.end;
+    }
+    if end < words.len() {
+        snippet.push('…');
+    }
+
+    snippet
+}
+
+fn escape_into(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indexed(name: &str, title: &str, body: &str) -> Indexed {
+        tagged(name, title, body, &[])
+    }
+
+    fn tagged(name: &str, title: &str, body: &str, tags: &[&str]) -> Indexed {
+        Indexed {
+            document: Document {
+                name: name.to_string(),
+                title: title.to_string(),
+                description: String::new(),
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                updated_at: OffsetDateTime::UNIX_EPOCH,
+            },
+            body: Some(body.to_string()),
+            failure: None,
+        }
+    }
+
+    #[test]
+    fn test_ranks_and_highlights_matches() {
+        let index = Index::build(vec![
+            indexed("a", "Cooking", "A note on rust <and> iron pans."),
+            indexed("b", "Learning Rust", "Notes on the Rust borrow checker."),
+            indexed("c", "Gardening", "Nothing to see here."),
+        ]);
+
+        let hits = index.search("RUST", 10);
+        let names: Vec<_> = hits.iter().map(|hit| hit.name.as_str()).collect();
+        assert_eq!(names, ["b", "a"]);
+        assert_eq!(
+            hits[1].snippet,
+            "A note on <mark>rust</mark> &lt;and&gt; iron pans"
+        );
+
+        let names: Vec<_> = index
+            .search("rust pans", 10)
+            .into_iter()
+            .map(|hit| hit.name)
+            .collect();
+        assert_eq!(names, ["a"]);
+        assert!(index.search("rust turnips", 10).is_empty());
+        assert!(index.search("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_ranks_related_posts() {
+        let index = Index::build(vec![
+            tagged(
+                "a",
+                "Rust lifetimes",
+                "Borrowing and lifetimes in Rust.",
+                &["rust"],
+            ),
+            tagged("b", "More Rust", "Lifetimes, borrowing, the checker.", &[]),
+            tagged("c", "Async", "Futures and executors.", &["rust"]),
+            tagged("d", "Gardening", "Tomatoes, soil, sunshine.", &["garden"]),
+        ]);
+
+        let names: Vec<_> = index
+            .related("a", 10)
+            .unwrap()
+            .into_iter()
+            .map(|related| related.name)
+            .collect();
+        assert_eq!(names, ["b", "c"]);
+        assert_eq!(index.related("a", 1).unwrap().len(), 1);
+        assert!(index.related("z", 10).is_none());
+
+        let draft = tagged("z", "Draft", "Tomatoes, soil.", &[]);
+        let names: Vec<_> = index
+            .including(draft.document, draft.body.unwrap())
+            .related("z", 10)
+            .unwrap()
+            .into_iter()
+            .map(|related| related.name)
+            .collect();
+        assert_eq!(names, ["d"]);
+    }
+}
@@ -0,0 +1,183 @@
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures::stream::{self, StreamExt};
+
+mod index;
+
+pub use index::{Document, Hit, Index};
+use index::{FetchFailure, Indexed};
+
+use crate::content::html_to_text;
+
+/// How many post bodies are fetched at once while rebuilding.
+const FETCH_CONCURRENCY: usize = 4;
+
+/// How long a post whose body failed to fetch is indexed without it before trying again. The wait
+/// doubles with every failure at the same revision, up to the max.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(3600);
+
+/// Full-text search over the writing. The index lives in memory and is rebuilt whenever the
+/// listing it was built from changes, reusing the text of every post that hasn't been updated so
+/// only new and changed posts are fetched again.
+#[derive(Clone, Default)]
+pub struct SearchIndex {
+    current: Arc<RwLock<Arc<Index>>>,
+    rebuilding: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An index over `documents`, rebuilding it first if they aren't what it was last built from.
+    /// `fetch_body` fetches a post's rendered HTML by name. A post whose body can't be fetched is
+    /// still indexed by its title and description, and its body is tried again once a backoff has
+    /// passed, so a post that keeps failing doesn't make every search rebuild the index.
+    pub async fn refresh<F, Fut, E>(&self, documents: Vec<Document>, fetch_body: F) -> Arc<Index>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<Bytes, E>>,
+        E: std::fmt::Display,
+    {
+        let current = self.current();
+        if current.is_current(&documents, Instant::now()) {
+            return current;
+        }
+
+        // Only one rebuild at a time, anyone waiting on it gets what it built.
+        let _rebuilding = self.rebuilding.lock().await;
+        let current = self.current();
+        let now = Instant::now();
+        if current.is_current(&documents, now) {
+            return current;
+        }
+
+        // Bodies we hold are reused, and so are failures still backing off.
+        let documents: Vec<Indexed> = documents
+            .into_iter()
+            .map(|document| {
+                let (body, failure) = current
+                    .revision(&document)
+                    .map(|indexed| (indexed.body.clone(), indexed.failure))
+                    .unwrap_or_default();
+                Indexed {
+                    document,
+                    body,
+                    failure,
+                }
+            })
+            .collect();
+        let is_due = |indexed: &Indexed| {
+            indexed.body.is_none()
+                && indexed
+                    .failure
+                    .map_or(true, |failure| failure.retry_at <= now)
+        };
+        tracing::debug!(
+            "rebuilding search index over {} posts, fetching {}",
+            documents.len(),
+            documents.iter().filter(|indexed| is_due(indexed)).count()
+        );
+
+        let indexed = stream::iter(documents)
+            .map(|indexed| {
+                let fetch = is_due(&indexed).then(|| fetch_body(indexed.document.name.clone()));
+
+                async move {
+                    let Some(fetch) = fetch else {
+                        return indexed;
+                    };
+                    match fetch.await {
+                        Ok(html) => Indexed {
+                            body: Some(html_to_text(&String::from_utf8_lossy(&html))),
+                            failure: None,
+                            ..indexed
+                        },
+                        Err(err) => {
+                            let attempts =
+                                indexed.failure.map_or(1, |failure| failure.attempts + 1);
+                            let delay = RETRY_BASE_DELAY
+                                .saturating_mul(1 << (attempts - 1).min(16))
+                                .min(RETRY_MAX_DELAY);
+                            tracing::warn!(
+                                "failed to index body of {}, retrying in {delay:?}: {err}",
+                                indexed.document.name
+                            );
+                            Indexed {
+                                failure: Some(FetchFailure {
+                                    attempts,
+                                    retry_at: Instant::now() + delay,
+                                }),
+                                ..indexed
+                            }
+                        }
+                    }
+                }
+            })
+            .buffered(FETCH_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        let index = Arc::new(Index::build(indexed));
+        *self.current.write().unwrap() = index.clone();
+        index
+    }
+
+    fn current(&self) -> Arc<Index> {
+        self.current.read().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    fn document(name: &str) -> Document {
+        Document {
+            name: name.to_string(),
+            title: name.to_string(),
+            description: String::new(),
+            tags: Vec::new(),
+            updated_at: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backs_off_failed_bodies() {
+        let search_index = SearchIndex::new();
+        let fetches = AtomicUsize::new(0);
+        let fetch_body = |name: String| {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match name.as_str() {
+                    "broken" => Err("not found"),
+                    _ => Ok(Bytes::from_static(b"<p>Some text.</p>")),
+                }
+            }
+        };
+
+        search_index
+            .refresh(vec![document("broken")], fetch_body)
+            .await;
+        search_index
+            .refresh(vec![document("broken")], fetch_body)
+            .await;
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // A new post is fetched, the broken one waits out its backoff.
+        let index = search_index
+            .refresh(vec![document("broken"), document("fine")], fetch_body)
+            .await;
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+        assert_eq!(index.search("text", 10).len(), 1);
+    }
+}
@@ -17,6 +17,7 @@ use tracing::Level;
 use crate::api;
 use crate::app::{AppState, AppStateSetupError};
 use crate::health;
+use crate::ssr::ShutdownState;
 use crate::web::WebApp;
 
 const HEALTH_ROUTE: &str = "/_status";
@@ -97,7 +98,7 @@ async fn leptos_routes_handler(
 pub async fn run(
     log_level: Level,
     state: AppState,
-    mut shutdown_rx: watch::Receiver<()>,
+    mut shutdown_rx: watch::Receiver<ShutdownState>,
 ) -> Result<(), HttpServerError> {
     let leptos_options = state.leptos_options.clone();
     let leptos_site_addr = leptos_options.site_addr;
@@ -112,11 +113,18 @@ pub async fn run(
         .on_failure(DefaultOnFailure::new().latency_unit(LatencyUnit::Micros));
 
     let leptos_routes = generate_route_list(WebApp);
+    let route_paths: Vec<String> = leptos_routes
+        .iter()
+        .map(|route| route.path().to_string())
+        .collect();
     let root_router = Router::new()
         .leptos_routes_with_handler(leptos_routes, get(leptos_routes_handler))
         .fallback(file_and_error_handler)
         .nest(HEALTH_ROUTE, health::router(state.clone()))
         .nest(API_ROUTE, api::router(state.clone()))
+        .merge(api::feed_router(state.clone()))
+        .merge(api::highlight_router(state.clone()))
+        .merge(api::crawl_router(state.clone(), &route_paths))
         .with_state(state)
         .layer(trace_layer);
 
@@ -125,7 +133,9 @@ pub async fn run(
 
     axum::serve(listener, root_router)
         .with_graceful_shutdown(async move {
-            let _ = shutdown_rx.changed().await;
+            let _ = shutdown_rx
+                .wait_for(|state| *state == ShutdownState::Stopping)
+                .await;
         })
         .await?;
 
@@ -0,0 +1,199 @@
+use std::fmt;
+
+/// The longest name we'll accept for a post or image.
+const MAX_LEN: usize = 128;
+
+/// The validated name of a post or image, as it appears in `/blog/:name` and `/gallery/:name`.
+///
+/// A canonical slug is lowercase and made up only of ASCII letters, digits, `-`, `_` and `.`,
+/// without a leading `.` or a `..` anywhere in it. That keeps names safe to splice into upstream
+/// paths, since nothing can add a path segment, a query string or a traversal.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Slug(String);
+
+impl Slug {
+    /// Validate `raw`, folding case and trailing-slash variants into the canonical slug.
+    pub fn canonicalize(raw: &str) -> Result<Self, SlugError> {
+        let slug = raw.trim_end_matches('/').to_ascii_lowercase();
+
+        if slug.is_empty() {
+            return Err(SlugError::Empty);
+        }
+        if slug.len() > MAX_LEN {
+            return Err(SlugError::TooLong);
+        }
+        if let Some(c) = slug
+            .chars()
+            .find(|c| !matches!(c, 'a'..='z' | '0'..='9' | '-' | '_' | '.'))
+        {
+            return Err(SlugError::InvalidChar(c));
+        }
+        if slug.starts_with('.') || slug.contains("..") {
+            return Err(SlugError::DotSegment);
+        }
+
+        Ok(Self(slug))
+    }
+
+    /// Whether `raw` is already a canonical slug, rather than merely one that can be folded into
+    /// one.
+    #[cfg(feature = "ssr")]
+    pub fn is_canonical(raw: &str) -> bool {
+        Self::canonicalize(raw).is_ok_and(|slug| slug.0 == raw)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for Slug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum SlugError {
+    #[error("name is empty")]
+    Empty,
+    #[error("name is longer than {MAX_LEN} characters")]
+    TooLong,
+    #[error("name contains {0:?}")]
+    InvalidChar(char),
+    #[error("name starts with a dot or contains '..'")]
+    DotSegment,
+}
+
+#[cfg(feature = "ssr")]
+mod extract {
+    use axum::async_trait;
+    use axum::extract::{FromRequestParts, OriginalUri, Path};
+    use axum::http::request::Parts;
+    use axum::http::{header, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::Json;
+
+    use super::{Slug, SlugError};
+
+    /// Extracts the route's single path parameter as a [`Slug`]. Invalid names are rejected with a
+    /// 400, and case or trailing-slash variants are sent to the canonical URL with a 301.
+    #[async_trait]
+    impl<S> FromRequestParts<S> for Slug
+    where
+        S: Send + Sync,
+    {
+        type Rejection = SlugRejection;
+
+        async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+            let Path(raw) = Path::<String>::from_request_parts(parts, state)
+                .await
+                .map_err(|_| SlugRejection::Undecodable)?;
+            let slug = Slug::canonicalize(&raw).map_err(SlugRejection::Invalid)?;
+
+            // Nested routers only see the tail of the path, the redirect needs all of it.
+            let uri = parts
+                .extensions
+                .get::<OriginalUri>()
+                .map(|original| &original.0)
+                .unwrap_or(&parts.uri);
+            let path = uri.path();
+            let canonical_path = path
+                .trim_end_matches('/')
+                .split('/')
+                .map(|segment| {
+                    if segment == raw {
+                        slug.as_str()
+                    } else {
+                        segment
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if canonical_path != path {
+                let location = match uri.query() {
+                    Some(query) => format!("{canonical_path}?{query}"),
+                    None => canonical_path,
+                };
+                return Err(SlugRejection::NotCanonical(location));
+            }
+
+            Ok(slug)
+        }
+    }
+
+    #[derive(Debug)]
+    pub enum SlugRejection {
+        Undecodable,
+        Invalid(SlugError),
+        NotCanonical(String),
+    }
+
+    impl IntoResponse for SlugRejection {
+        fn into_response(self) -> Response {
+            let error_message = match self {
+                SlugRejection::NotCanonical(location) => {
+                    return (
+                        StatusCode::MOVED_PERMANENTLY,
+                        [(header::LOCATION, location)],
+                    )
+                        .into_response();
+                }
+                SlugRejection::Undecodable => "invalid name: not valid UTF-8".to_string(),
+                SlugRejection::Invalid(err) => format!("invalid name: {err}"),
+            };
+
+            let err_msg = serde_json::json!({"msg": error_message});
+            (StatusCode::BAD_REQUEST, Json(err_msg)).into_response()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalizes_slugs() {
+        assert_eq!(
+            Slug::canonicalize("hello-world").unwrap().as_str(),
+            "hello-world"
+        );
+        assert_eq!(
+            Slug::canonicalize("dot_1.png").unwrap().as_str(),
+            "dot_1.png"
+        );
+        assert_eq!(
+            Slug::canonicalize("Hello-World/").unwrap().as_str(),
+            "hello-world"
+        );
+        assert!(Slug::is_canonical("hello-world"));
+        assert!(!Slug::is_canonical("Hello-World"));
+    }
+
+    #[test]
+    fn test_rejects_unsafe_slugs() {
+        assert_eq!(Slug::canonicalize(""), Err(SlugError::Empty));
+        assert_eq!(Slug::canonicalize(".."), Err(SlugError::DotSegment));
+        assert_eq!(Slug::canonicalize(".env"), Err(SlugError::DotSegment));
+        assert_eq!(Slug::canonicalize("a..b"), Err(SlugError::DotSegment));
+        assert_eq!(Slug::canonicalize("a/b"), Err(SlugError::InvalidChar('/')));
+        assert_eq!(
+            Slug::canonicalize("a?html=false"),
+            Err(SlugError::InvalidChar('?'))
+        );
+        assert_eq!(
+            Slug::canonicalize("a%2fb"),
+            Err(SlugError::InvalidChar('%'))
+        );
+        assert_eq!(
+            Slug::canonicalize(&"a".repeat(MAX_LEN + 1)),
+            Err(SlugError::TooLong)
+        );
+    }
+}
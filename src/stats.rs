@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use bytes::Bytes;
+use lol_html::html_content::{Element, TextChunk};
+use lol_html::{element, text, RewriteStrSettings};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::content::html_to_text;
+
+/// The reading speed reading times are estimated at.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// How many words an excerpt runs to.
+const EXCERPT_WORDS: usize = 40;
+
+/// How long a post is, and a taste of what it says.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PostStats {
+    pub word_count: usize,
+    /// Whole minutes, never less than one.
+    pub reading_time_minutes: usize,
+    /// The opening words of the post's paragraphs as plain text, ending in `…` if cut short.
+    pub excerpt: String,
+}
+
+impl PostStats {
+    /// The stats of a post rendered to `html`.
+    pub fn from_html(html: &str) -> Self {
+        let word_count = html_to_text(html).split_whitespace().count();
+
+        // Headings, lists and code read poorly out of context, so excerpts are made from the
+        // paragraphs alone. A post without any falls back to all of its text.
+        let mut excerpt_words = paragraph_text(html)
+            .filter(|text| !text.is_empty())
+            .unwrap_or_else(|| html_to_text(html))
+            .split_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let truncated = excerpt_words.len() > EXCERPT_WORDS;
+        excerpt_words.truncate(EXCERPT_WORDS);
+        let mut excerpt = excerpt_words.join(" ");
+        if truncated {
+            excerpt.push('…');
+        }
+
+        Self {
+            word_count,
+            reading_time_minutes: word_count.div_ceil(WORDS_PER_MINUTE).max(1),
+            excerpt,
+        }
+    }
+}
+
+/// The text of every `<p>` in `html`, or `None` if it can't be read.
+fn paragraph_text(html: &str) -> Option<String> {
+    let raw = Rc::new(RefCell::new(String::new()));
+    let on_paragraph = {
+        let raw = raw.clone();
+        move |_: &mut Element| {
+            raw.borrow_mut().push(' ');
+            Ok(())
+        }
+    };
+    let on_text = {
+        let raw = raw.clone();
+        move |chunk: &mut TextChunk| {
+            raw.borrow_mut().push_str(chunk.as_str());
+            Ok(())
+        }
+    };
+
+    let read = lol_html::rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![element!("p", on_paragraph), text!("p", on_text)],
+            ..RewriteStrSettings::default()
+        },
+    );
+    if let Err(err) = read {
+        tracing::warn!("failed to read the paragraphs of a post: {err}");
+        return None;
+    }
+
+    // The chunks are still escaped, which `html_to_text` takes care of.
+    let raw = raw.take();
+    Some(html_to_text(&raw))
+}
+
+/// The stats of every post, computed once for each version of it.
+#[derive(Clone, Default)]
+pub struct PostStatsCache {
+    /// The stats of each post by name, along with the `updated_at` they were computed for.
+    entries: Arc<RwLock<HashMap<String, (OffsetDateTime, PostStats)>>>,
+}
+
+impl PostStatsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stats of the post called `name` as of `updated_at`. The first time a version is asked
+    /// for they're computed from its HTML, fetched with `fetch_html`, replacing those of any
+    /// earlier version.
+    pub async fn get<F, Fut, E>(
+        &self,
+        name: &str,
+        updated_at: OffsetDateTime,
+        fetch_html: F,
+    ) -> Result<PostStats, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Bytes, E>>,
+    {
+        let cached = self.entries.read().unwrap().get(name).cloned();
+        if let Some((cached_at, stats)) = cached {
+            if cached_at == updated_at {
+                return Ok(stats);
+            }
+        }
+
+        let html = fetch_html().await?;
+        let stats = PostStats::from_html(&String::from_utf8_lossy(&html));
+        self.entries
+            .write()
+            .unwrap()
+            .insert(name.to_string(), (updated_at, stats.clone()));
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_stats() {
+        let paragraph = "word ".repeat(40);
+        let html = format!(
+            "<h2>Heading</h2><p>Tom &amp; Jerry</p><pre><code>let x = 1;</code></pre><p>{paragraph}</p>"
+        );
+
+        let stats = PostStats::from_html(&html);
+        assert_eq!(stats.word_count, 1 + 3 + 4 + 40);
+        assert_eq!(stats.reading_time_minutes, 1);
+        assert!(stats.excerpt.starts_with("Tom & Jerry word word"));
+        assert!(stats.excerpt.ends_with("word…"));
+        assert_eq!(stats.excerpt.split_whitespace().count(), EXCERPT_WORDS);
+
+        let long = format!("<p>{}</p>", "word ".repeat(401));
+        assert_eq!(PostStats::from_html(&long).reading_time_minutes, 3);
+        assert_eq!(
+            PostStats::from_html("<ul><li>Only</li></ul>").excerpt,
+            "Only"
+        );
+    }
+}
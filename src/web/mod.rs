@@ -5,7 +5,10 @@ use leptos_router::*;
 mod error;
 mod pages;
 
-use pages::{AboutPage, BlogPage, BlogPost, ErrorPage, GalleryImage, GalleryPage, HomePage};
+use pages::{
+    AboutPage, BlogPage, BlogPost, BlogTagPage, ErrorPage, GalleryImage, GalleryPage, HomePage,
+    SearchPage,
+};
 
 pub use error::WebAppError;
 
@@ -51,10 +54,18 @@ pub fn WebApp() -> impl IntoView {
       // injects a stylesheet into the document <head>
       // id=leptos means cargo-leptos willa hot-reload this stylesheet
       <Stylesheet id="leptos" href="/assets/corpo.css"/>
+      // colours for the code blocks highlighted in posts
+      <Stylesheet id="highlight" href="/highlight.css"/>
 
       // sets the document title
       <Title text="Krondor"/>
 
+      // advertises the feeds to readers
+      <Link rel="alternate" type_="application/rss+xml" title="Krondor" href="/feed.xml"/>
+      <Link rel="alternate" type_="application/atom+xml" title="Krondor" href="/atom.xml"/>
+      <Link rel="alternate" type_="application/feed+json" title="Krondor" href="/feed.json"/>
+      <Link rel="alternate" type_="application/rss+xml" title="Krondor gallery" href="/gallery/feed.xml"/>
+
       // content for this welcome page
       <Router fallback=|| {
             let mut outside_errors = Errors::default();
@@ -76,6 +87,7 @@ pub fn WebApp() -> impl IntoView {
                           <li><A href="https://docs.google.com/forms/d/1V16MGOz2V-JvivA7EMeaS-O-4c1duojn6V8hsGRcM2k/prefill">Get in Touch</A></li>
                           <li><A href="blog">Blog</A></li>
                           <li><A href="gallery">Gallery</A></li>
+                          <li><A href="search">Search</A></li>
                       </ul>
                   </nav>
                   <span
@@ -104,9 +116,14 @@ pub fn WebApp() -> impl IntoView {
                 <Route path="" view=HomePage/>
                 <Route path="about" view=AboutPage/>
                 <Route path="blog" view=BlogPage/>
+                <Route path="blog/tag/:tag" view=BlogTagPage/>
                 <Route path="blog/:name" view=BlogPost/>
+                // Only here so the post can redirect to its canonical URL.
+                <Route path="blog/:name/" view=BlogPost trailing_slash=TrailingSlash::Exact/>
                 <Route path="gallery" view=GalleryPage/>
                 <Route path="gallery/:name" view=GalleryImage/>
+                <Route path="gallery/:name/" view=GalleryImage trailing_slash=TrailingSlash::Exact/>
+                <Route path="search" view=SearchPage/>
               </Routes>
           </main>
       </Router>
@@ -1,47 +1,182 @@
 use leptos::*;
 use leptos_router::*;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use time::format_description;
 use time::OffsetDateTime;
+use wasm_bindgen::JsCast;
+
+use super::listing::fetch_page;
+use super::slug::use_slug;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Post {
     name: String,
     title: String,
     description: String,
+    #[serde(default)]
+    tags: Vec<String>,
     created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    #[serde(default)]
+    word_count: Option<usize>,
+    #[serde(default)]
+    reading_time_minutes: Option<usize>,
+    #[serde(default)]
+    excerpt: Option<String>,
+}
+
+/// A section of a post, as listed in its table of contents.
+#[derive(Clone, Debug, Deserialize)]
+struct Heading {
+    level: u8,
+    id: String,
+    text: String,
+}
+
+/// Another post worth reading after this one.
+#[derive(Clone, Debug, Deserialize)]
+struct RelatedPost {
+    name: String,
+    title: String,
+    description: String,
+}
+
+/// A post to link to from another.
+#[derive(Clone, Debug, Deserialize)]
+struct PostLink {
+    name: String,
+    title: String,
+}
+
+/// Every part of a series, in reading order.
+#[derive(Clone, Debug, Deserialize)]
+struct Series {
+    series: String,
+    parts: Vec<PostLink>,
+}
+
+/// Where to go from a post: the posts published either side of it, and the rest of its series.
+#[derive(Clone, Debug, Deserialize)]
+struct PostNav {
+    previous: Option<PostLink>,
+    next: Option<PostLink>,
+    series: Option<Series>,
+}
+
+/// How far below the top of the window a heading can be and still count as the one being read,
+/// matching the scroll margin headings are given.
+const SECTION_MARGIN: f64 = 96.0;
+
+fn format_date(date: OffsetDateTime) -> String {
+    let time_format = format_description::parse("[year]-[month]-[day]").unwrap();
+    date.format(&time_format).unwrap()
+}
+
+/// The publication date of a post, plus when it was last updated if that was on a later day.
+#[component]
+fn PostDates(created_at: OffsetDateTime, updated_at: OffsetDateTime) -> impl IntoView {
+    let updated_on = (updated_at.date() != created_at.date())
+        .then(|| format!(" · updated on {}", format_date(updated_at)));
+
+    view! {
+        <p class="text-sm text-gray-500">
+            {format_date(created_at)}
+            {updated_on}
+        </p>
+    }
+}
+
+/// How long a post is and how long it takes to read, when the listing knows.
+#[component]
+fn ReadingTime(word_count: Option<usize>, minutes: Option<usize>) -> impl IntoView {
+    let (Some(word_count), Some(minutes)) = (word_count, minutes) else {
+        return ().into_view();
+    };
+
+    view! {
+        <p class="text-sm text-gray-500">
+            {format!("{} words · {} min read", word_count, minutes)}
+        </p>
+    }
+    .into_view()
+}
+
+/// Links to the listing of every post carrying each of `tags`.
+#[component]
+fn TagChips(tags: Vec<String>) -> impl IntoView {
+    view! {
+        <ul class="flex flex-wrap gap-2">
+            {tags.into_iter().map(|tag| view! {
+                <li>
+                    <A href=format!("/blog/tag/{}", tag)
+                       class="inline-block px-2 py-0.5 text-xs font-mono bg-gray-100 border border-gray-300 rounded
+                              hover:bg-black hover:text-white">
+                        {format!("#{}", tag)}
+                    </A>
+                </li>
+            }).collect::<Vec<_>>()}
+        </ul>
+    }
 }
 
 #[component]
 pub fn BlogPage() -> impl IntoView {
-    let (posts, set_posts) = create_signal(Vec::new());
+    view! { <BlogListing heading="> blog stuff"/> }
+}
+
+#[component]
+pub fn BlogTagPage() -> impl IntoView {
+    let params = use_params_map();
+    let tag = move || params.with(|params| params.get("tag").cloned().unwrap_or_default());
+
+    move || {
+        let tag = tag();
+        view! { <BlogListing heading=format!("> tagged #{}", tag) tag/> }
+    }
+}
+
+/// Every post, or only those tagged `tag`, newest first and loaded a page at a time.
+#[component]
+fn BlogListing(
+    #[prop(into)] heading: String,
+    #[prop(optional)] tag: Option<String>,
+) -> impl IntoView {
+    let tag = store_value(tag);
+    let (posts, set_posts) = create_signal(Vec::<Post>::new());
+    let (next_cursor, set_next_cursor) = create_signal(None::<String>);
+    let (total, set_total) = create_signal(0);
     let (loading, set_loading) = create_signal(true);
+    let (loading_more, set_loading_more) = create_signal(false);
     let (error, set_error) = create_signal(None::<String>);
 
-    create_effect(move |_| {
+    let load_page = move |cursor: Option<String>| {
         spawn_local(async move {
-            let client = Client::new();
-            let url = format!("{}/api/v0/blog", window().location().origin().unwrap());
-            match client.get(&url).send().await {
-                Ok(response) => match response.json::<Vec<Post>>().await {
-                    Ok(mut fetched_posts) => {
-                        fetched_posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-                        set_posts.set(fetched_posts);
-                        set_loading.set(false);
-                    }
-                    Err(e) => {
-                        set_error.set(Some(format!("Failed to parse JSON: {}", e)));
-                        set_loading.set(false);
-                    }
-                },
-                Err(e) => {
-                    set_error.set(Some(format!("Failed to send request: {}", e)));
-                    set_loading.set(false);
+            let mut query = vec![("sort", "created".to_string())];
+            if let Some(tag) = tag.get_value() {
+                query.push(("tag", tag));
+            }
+
+            match fetch_page::<Post>("/api/v0/blog", &query, cursor.as_deref()).await {
+                Ok(page) => {
+                    set_posts.update(|posts| posts.extend(page.items));
+                    set_next_cursor.set(page.next_cursor);
+                    set_total.set(page.total);
                 }
+                Err(e) => set_error.set(Some(e)),
             }
+            set_loading.set(false);
+            set_loading_more.set(false);
         });
-    });
+    };
+
+    create_effect(move |_| load_page(None));
+
+    let load_more = move |_| {
+        set_loading_more.set(true);
+        load_page(next_cursor.get_untracked());
+    };
 
     view! {
         <div class="min-h-screen flex flex-col">
@@ -49,7 +184,7 @@ pub fn BlogPage() -> impl IntoView {
                 <div class="max-w-3xl mx-auto px-4 py-8">
                     <h1
                         class="relative font-mono text-4xl font-bold mb-8 before:absolute before:inset-0 before:animate-typewriter before:bg-white after:absolute after:inset-0 after:w-[0.125em] after:animate-caret after:bg-black">
-                        "> blog stuff"
+                        {heading}
                     </h1>
                     {move || {
                         if loading.get() {
@@ -66,19 +201,34 @@ pub fn BlogPage() -> impl IntoView {
                                     {posts.get().into_iter().map(|post| view! {
                                         <li class="bg-white rounded-lg overflow-hidden shadow-md hover:shadow-xl
                                                    transition-all duration-300 ease-in-out transform hover:scale-102">
-                                            <A href=format!("/blog/{}", post.name) class="block p-6">
+                                            <A href=format!("/blog/{}", post.name) class="block p-6 pb-3">
                                                 <h2 class="text-2xl font-bold mb-2">{post.title}</h2>
                                                 <p class="text-gray-600 mb-2">{post.description}</p>
-                                                <p class="text-sm text-gray-500">
-                                                {
-                                                    let time_format = format_description::parse("[year]-[month]-[day]").unwrap();
-                                                    post.created_at.format(&time_format).unwrap()
-                                                }
-                                                </p>
+                                                {post.excerpt.map(|excerpt| view! {
+                                                    <p class="text-sm text-gray-500 mb-2 line-clamp-3">{excerpt}</p>
+                                                })}
+                                                <PostDates created_at=post.created_at updated_at=post.updated_at/>
+                                                <ReadingTime word_count=post.word_count minutes=post.reading_time_minutes/>
                                             </A>
+                                            <div class="px-6 pb-6">
+                                                <TagChips tags=post.tags/>
+                                            </div>
                                         </li>
                                     }).collect::<Vec<_>>()}
                                 </ul>
+                                <Show when=move || next_cursor.get().is_some()>
+                                    <div class="flex flex-col items-center mt-8 gap-2">
+                                        <button
+                                            class="px-4 py-2 font-mono border-2 border-black rounded hover:bg-black hover:text-white disabled:opacity-50"
+                                            disabled=loading_more
+                                            on:click=load_more>
+                                            {move || if loading_more.get() { "loading..." } else { "> load more" }}
+                                        </button>
+                                        <p class="text-sm text-gray-500">
+                                            {move || format!("{} of {}", posts.with(Vec::len), total.get())}
+                                        </p>
+                                    </div>
+                                </Show>
                             }.into_view()
                         }
                     }}
@@ -88,65 +238,300 @@ pub fn BlogPage() -> impl IntoView {
     }
 }
 
+/// The sections of a post, kept in view beside it on wide screens with the one being read picked
+/// out.
+#[component]
+fn TableOfContents(
+    headings: ReadSignal<Vec<Heading>>,
+    active: ReadSignal<Option<String>>,
+) -> impl IntoView {
+    view! {
+        <Show when=move || headings.with(|headings| !headings.is_empty())>
+            <aside class="hidden lg:block w-56 shrink-0 sticky top-8 max-h-[calc(100vh-4rem)] overflow-y-auto font-mono text-sm">
+                <p class="font-bold mb-2">"> contents"</p>
+                <nav>
+                    <ul class="border-l-2 border-gray-200">
+                        {move || headings.get().into_iter().map(|heading| {
+                            let indent = if heading.level > 2 { "pl-6" } else { "pl-3" };
+                            let id = heading.id.clone();
+                            let class = move || {
+                                let state = if active.with(|active| active.as_deref() == Some(id.as_str())) {
+                                    "border-black text-black font-bold"
+                                } else {
+                                    "border-transparent text-gray-500 hover:text-black"
+                                };
+                                format!("block py-1 -ml-0.5 border-l-2 {indent} {state}")
+                            };
+                            view! {
+                                <li>
+                                    <a href=format!("#{}", heading.id) class=class>{heading.text}</a>
+                                </li>
+                            }
+                        }).collect::<Vec<_>>()}
+                    </ul>
+                </nav>
+            </aside>
+        </Show>
+    }
+}
+
+/// The parts of the series a post belongs to, with the post itself picked out, and links to the
+/// posts published before and after it.
+#[component]
+fn PostNavigation(nav: ReadSignal<Option<PostNav>>, current: String) -> impl IntoView {
+    move || {
+        let PostNav {
+            previous,
+            next,
+            series,
+        } = nav.get()?;
+        let current = current.clone();
+
+        Some(view! {
+            <nav class="not-prose mt-12 space-y-6 font-mono">
+                {series.map(|series| view! {
+                    <section class="p-4 bg-gray-50 border-l-4 border-gray-300 rounded-r-lg">
+                        <p class="font-bold mb-2">{format!("> series: {}", series.series)}</p>
+                        <ol class="list-decimal pl-6 space-y-1 text-sm">
+                            {series.parts.into_iter().map(|part| if part.name == current {
+                                view! { <li class="font-bold">{part.title}</li> }
+                            } else {
+                                view! {
+                                    <li>
+                                        <A href=format!("/blog/{}", part.name) class="hover:underline">{part.title}</A>
+                                    </li>
+                                }
+                            }).collect::<Vec<_>>()}
+                        </ol>
+                    </section>
+                })}
+                <div class="flex justify-between gap-4 text-sm">
+                    <div>
+                        {previous.map(|post| view! {
+                            <A href=format!("/blog/{}", post.name) class="hover:underline">{format!("< {}", post.title)}</A>
+                        })}
+                    </div>
+                    <div class="text-right">
+                        {next.map(|post| view! {
+                            <A href=format!("/blog/{}", post.name) class="hover:underline">{format!("{} >", post.title)}</A>
+                        })}
+                    </div>
+                </div>
+            </nav>
+        })
+    }
+}
+
+/// Posts like the one just read, offered at the end of it.
+#[component]
+fn RelatedReading(related: ReadSignal<Vec<RelatedPost>>) -> impl IntoView {
+    view! {
+        <Show when=move || related.with(|related| !related.is_empty())>
+            <section class="not-prose mt-12 pt-6 border-t-2 border-gray-200">
+                <h2 class="font-mono font-bold text-lg mb-4">"> related reading"</h2>
+                <ul class="space-y-4">
+                    {move || related.get().into_iter().map(|post| view! {
+                        <li>
+                            <A href=format!("/blog/{}", post.name) class="block group">
+                                <span class="font-bold group-hover:underline">{post.title}</span>
+                                <p class="text-sm text-gray-600">{post.description}</p>
+                            </A>
+                        </li>
+                    }).collect::<Vec<_>>()}
+                </ul>
+            </section>
+        </Show>
+    }
+}
+
+/// The section being read: the last heading scrolled up to the top of the window, or the first
+/// if none has been yet.
+fn current_section(headings: &[Heading]) -> Option<String> {
+    let threshold = window().scroll_y().unwrap_or_default() + SECTION_MARGIN;
+    let document = document();
+
+    headings
+        .iter()
+        .take_while(|heading| {
+            document
+                .get_element_by_id(&heading.id)
+                .and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok())
+                .is_some_and(|element| f64::from(element.offset_top()) <= threshold)
+        })
+        .last()
+        .or(headings.first())
+        .map(|heading| heading.id.clone())
+}
+
+/// The post called `name`, or `None` if there's no such post. `preview_query` carries the preview
+/// token, if any, which lets an unpublished post through.
+async fn fetch_item(
+    client: &Client,
+    name: &str,
+    preview_query: &[(&str, String)],
+) -> Result<Option<Post>, String> {
+    let url = format!(
+        "{}/api/v0/blog/{}/item",
+        window().location().origin().unwrap(),
+        name
+    );
+    let response = client
+        .get(&url)
+        .query(preview_query)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send post request: {}", e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    response
+        .json::<Post>()
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to read post: {}", e))
+}
+
+/// One of the extras shown around a post, or `None` if it couldn't be had, which the post reads fine
+/// without.
+async fn fetch_json<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    preview_query: &[(&str, String)],
+) -> Option<T> {
+    let response = client.get(url).query(preview_query).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response.json::<T>().await.ok()
+}
+
 #[component]
 pub fn BlogPost() -> impl IntoView {
-    let params = use_params_map();
-    let post_name = move || params.with(|params| params.get("name").cloned().unwrap_or_default());
+    let slug = use_slug("blog");
+    let query = use_query_map();
+    let preview = move || query.with(|query| query.get("preview").cloned());
 
     let (post, set_post) = create_signal(None::<Post>);
     let (content, set_content) = create_signal(String::new());
+    let (headings, set_headings) = create_signal(Vec::<Heading>::new());
+    let (active, set_active) = create_signal(None::<String>);
+    let (related, set_related) = create_signal(Vec::<RelatedPost>::new());
+    let (nav, set_nav) = create_signal(None::<PostNav>);
     let (loading, set_loading) = create_signal(true);
     let (error, set_error) = create_signal(None::<String>);
 
+    let scroll_handle = window_event_listener(ev::scroll, move |_| {
+        set_active.set(headings.with_untracked(|headings| current_section(headings)));
+    });
+    on_cleanup(move || scroll_handle.remove());
+
+    // Bumped for every post loaded, so responses for one we've since moved away from are ignored.
+    let generation = store_value(0u64);
+
     create_effect(move |_| {
-        let name = post_name();
+        // Moving between posts keeps this component mounted, so nothing of the last one may linger.
+        set_loading.set(true);
+        set_error.set(None);
+        set_post.set(None);
+        set_content.set(String::new());
+        set_headings.set(Vec::new());
+        set_active.set(None);
+        set_related.set(Vec::new());
+        set_nav.set(None);
+
+        generation.update_value(|generation| *generation += 1);
+        let this_generation = generation.get_value();
+        let is_current = move || generation.try_get_value() == Some(this_generation);
+
+        let name = match slug.get() {
+            Ok(slug) => slug.into_string(),
+            Err(err) => {
+                set_error.set(Some(format!("Invalid post name: {}", err)));
+                set_loading.set(false);
+                return;
+            }
+        };
+        let preview = preview();
         spawn_local(async move {
             let client = Client::new();
-            let metadataUrl = format!("{}/api/v0/blog", window().location().origin().unwrap());
-            match client.get(&metadataUrl).send().await {
-                Ok(response) => match response.json::<Vec<Post>>().await {
-                    Ok(posts) => {
-                        if let Some(found_post) = posts.into_iter().find(|p| p.name == name) {
-                            set_post.set(Some(found_post));
-
-                            // Fetch post content
-                            let contentUrl = format!(
-                                "{}/api/v0/blog/{}",
-                                window().location().origin().unwrap(),
-                                name
-                            );
-                            match client.get(&contentUrl).send().await {
-                                Ok(response) => match response.text().await {
-                                    Ok(fetched_content) => {
-                                        set_content.set(fetched_content);
-                                        set_loading.set(false);
-                                    }
-                                    Err(e) => {
-                                        set_error
-                                            .set(Some(format!("Failed to fetch content: {}", e)));
-                                        set_loading.set(false);
-                                    }
-                                },
-                                Err(e) => {
-                                    set_error.set(Some(format!(
-                                        "Failed to send content request: {}",
-                                        e
-                                    )));
-                                    set_loading.set(false);
-                                }
+            let preview_query: Vec<(&str, String)> = preview
+                .map(|token| vec![("preview", token)])
+                .unwrap_or_default();
+            let found = fetch_item(&client, &name, &preview_query).await;
+            if !is_current() {
+                return;
+            }
+            match found {
+                Ok(Some(found_post)) => {
+                    set_post.set(Some(found_post));
+
+                    // Fetch post content
+                    let contentUrl = format!(
+                        "{}/api/v0/blog/{}",
+                        window().location().origin().unwrap(),
+                        name
+                    );
+                    let fetched_content =
+                        match client.get(&contentUrl).query(&preview_query).send().await {
+                            Ok(response) if response.status().is_success() => response
+                                .text()
+                                .await
+                                .map_err(|e| format!("Failed to fetch content: {}", e)),
+                            Ok(response) => {
+                                Err(format!("Failed to fetch content: {}", response.status()))
                             }
-                        } else {
-                            set_error.set(Some("Post not found".to_string()));
-                            set_loading.set(false);
+                            Err(e) => Err(format!("Failed to send content request: {}", e)),
+                        };
+                    if !is_current() {
+                        return;
+                    }
+                    match fetched_content {
+                        Ok(fetched_content) => set_content.set(fetched_content),
+                        Err(e) => set_error.set(Some(e)),
+                    }
+                    set_loading.set(false);
+
+                    // The table of contents is a nicety, a post reads fine without one.
+                    let tocUrl = format!("{}/toc", contentUrl);
+                    if let Some(fetched_headings) =
+                        fetch_json::<Vec<Heading>>(&client, &tocUrl, &preview_query).await
+                    {
+                        if !is_current() {
+                            return;
                         }
+                        set_active.set(fetched_headings.first().map(|heading| heading.id.clone()));
+                        set_headings.set(fetched_headings);
                     }
-                    Err(e) => {
-                        set_error.set(Some(format!("Failed to parse JSON: {}", e)));
-                        set_loading.set(false);
+
+                    // As are links to the posts around this one.
+                    let navUrl = format!("{}/nav", contentUrl);
+                    if let Some(fetched_nav) =
+                        fetch_json::<PostNav>(&client, &navUrl, &preview_query).await
+                    {
+                        if !is_current() {
+                            return;
+                        }
+                        set_nav.set(Some(fetched_nav));
                     }
-                },
+
+                    // And recommendations.
+                    let relatedUrl = format!("{}/related", contentUrl);
+                    if let Some(fetched_related) =
+                        fetch_json::<Vec<RelatedPost>>(&client, &relatedUrl, &preview_query).await
+                    {
+                        if !is_current() {
+                            return;
+                        }
+                        set_related.set(fetched_related);
+                    }
+                }
+                Ok(None) => {
+                    set_error.set(Some("Post not found".to_string()));
+                    set_loading.set(false);
+                }
                 Err(e) => {
-                    set_error.set(Some(format!("Failed to send request: {}", e)));
+                    set_error.set(Some(e));
                     set_loading.set(false);
                 }
             }
@@ -156,7 +541,7 @@ pub fn BlogPost() -> impl IntoView {
     view! {
         <div class="min-h-screen flex flex-col">
             <div class="flex-grow overflow-y-auto">
-                <div class="max-w-3xl mx-auto px-4 py-8">
+                <div class="max-w-3xl lg:max-w-5xl mx-auto px-4 py-8">
                     {move || {
                         if loading.get() {
                             view! { <p class="text-center text-lg">"Loading..."</p> }.into_view()
@@ -164,29 +549,48 @@ pub fn BlogPost() -> impl IntoView {
                             view! { <p class="text-center text-red-500">"Error: " {err}</p> }.into_view()
                         } else if let Some(post) = post.get() {
                             view! {
-                                <article class="prose lg:prose-xl max-w-none">
-                                    <div class="mb-8 p-6 bg-gray-50 border-l-4 border-gray-300 rounded-r-lg shadow-sm">
-                                        <h1 class="text-4xl font-bold mb-3">{post.title}</h1>
-                                        <p class="text-xl text-gray-600 mb-2">{post.description}</p>
-                                        <p class="text-sm text-gray-500">
-                                        {
-                                            let time_format = format_description::parse("[year]-[month]-[day]").unwrap();
-                                            post.created_at.format(&time_format).unwrap()
-                                        }
-                                        </p>
-                                    </div>
-                                    <div
-                                        class="[&>p]:mb-6 [&>h2]:text-2xl [&>h2]:font-bold [&>h2]:mt-8 [&>h2]:mb-4
-                                               [&>h3]:text-xl [&>h3]:font-bold [&>h3]:mt-6 [&>h3]:mb-3
-                                               [&>img]:mx-auto [&>img]:my-8
-                                               [&>pre]:bg-gray-100 [&>pre]:p-4 [&>pre]:rounded-md [&>pre]:overflow-x-auto
-                                               [&>pre]:text-gray-800 [&>pre]:border [&>pre]:border-gray-300
-                                               [&>:not(pre)>code]:bg-gray-200 [&>:not(pre)>code]:text-gray-800 
-                                               [&>:not(pre)>code]:px-1 [&>:not(pre)>code]:py-0.5 [&>:not(pre)>code]:rounded
-                                               [&>:not(pre)>code]:border [&>:not(pre)>code]:border-gray-300"
-                                        inner_html=content.get()
-                                    />
-                                </article>
+                                <div class="lg:flex lg:gap-8 lg:items-start">
+                                    <article class="prose lg:prose-xl max-w-none min-w-0 flex-1">
+                                        <div class="mb-8 p-6 bg-gray-50 border-l-4 border-gray-300 rounded-r-lg shadow-sm">
+                                            {move || preview().map(|_| view! {
+                                                <p class="font-mono text-sm text-orange-700 mb-2">"> preview: not published yet"</p>
+                                            })}
+                                            <h1 class="text-4xl font-bold mb-3">{post.title}</h1>
+                                            <p class="text-xl text-gray-600 mb-2">{post.description}</p>
+                                            <PostDates created_at=post.created_at updated_at=post.updated_at/>
+                                            <ReadingTime word_count=post.word_count minutes=post.reading_time_minutes/>
+                                            <TagChips tags=post.tags/>
+                                        </div>
+                                        <div
+                                            class="[&>p]:mb-6 [&>h2]:text-2xl [&>h2]:font-bold [&>h2]:mt-8 [&>h2]:mb-4
+                                                   [&>h3]:text-xl [&>h3]:font-bold [&>h3]:mt-6 [&>h3]:mb-3
+                                                   [&>img]:mx-auto [&>img]:my-8
+                                                   [&>pre]:bg-gray-100 [&>pre]:p-4 [&>pre]:rounded-md [&>pre]:overflow-x-auto
+                                                   [&>pre]:text-gray-800 [&>pre]:border [&>pre]:border-gray-300
+                                                   [&>:not(pre)>code]:bg-gray-200 [&>:not(pre)>code]:text-gray-800 
+                                                   [&>:not(pre)>code]:px-1 [&>:not(pre)>code]:py-0.5 [&>:not(pre)>code]:rounded
+                                                   [&>:not(pre)>code]:border [&>:not(pre)>code]:border-gray-300
+                                                   [&_:is(h1,h2,h3,h4)]:scroll-mt-20
+                                                   [&_.heading-anchor]:ml-2 [&_.heading-anchor]:text-gray-400 [&_.heading-anchor]:no-underline
+                                                   [&_.heading-anchor]:opacity-0 [&_.heading-anchor:focus]:opacity-100 [&_:is(h2,h3):hover_.heading-anchor]:opacity-100
+                                                   [&_.heading-anchor]:before:content-['#']
+                                                   [&>ul]:list-disc [&>ul]:pl-6 [&>ul]:mb-6 [&>ol]:list-decimal [&>ol]:pl-6 [&>ol]:mb-6
+                                                   [&_li:has(>input[type=checkbox])]:list-none [&_input[type=checkbox]]:mr-2
+                                                   [&>table]:w-full [&>table]:mb-6 [&>table]:border-collapse
+                                                   [&_th]:border [&_th]:border-gray-300 [&_th]:bg-gray-100 [&_th]:px-3 [&_th]:py-1
+                                                   [&_td]:border [&_td]:border-gray-300 [&_td]:px-3 [&_td]:py-1
+                                                   [&_del]:text-gray-500
+                                                   [&_.footnote-reference]:text-xs
+                                                   [&_.footnote-definition]:text-sm [&_.footnote-definition]:text-gray-600
+                                                   [&_.footnote-definition]:border-t [&_.footnote-definition]:border-gray-200 [&_.footnote-definition]:pt-2
+                                                   [&_.footnote-definition>p]:inline [&_.footnote-definition-label]:mr-2"
+                                            inner_html=content.get()
+                                        />
+                                        <PostNavigation nav current=post.name/>
+                                        <RelatedReading related/>
+                                    </article>
+                                    <TableOfContents headings active/>
+                                </div>
                             }.into_view()
                         } else {
                             view! { <p class="text-center text-lg">"Post not found"</p> }.into_view()
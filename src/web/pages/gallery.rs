@@ -2,6 +2,10 @@ use leptos::*;
 use leptos_router::*;
 use serde::{Deserialize, Serialize};
 
+use super::listing::fetch_page;
+use super::slug::use_slug;
+use crate::slug::Slug;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ImageMetadata {
     name: String,
@@ -9,32 +13,35 @@ pub struct ImageMetadata {
 
 #[component]
 pub fn GalleryPage() -> impl IntoView {
-    let (images, set_images) = create_signal(Vec::new());
+    let (images, set_images) = create_signal(Vec::<ImageMetadata>::new());
+    let (next_cursor, set_next_cursor) = create_signal(None::<String>);
+    let (total, set_total) = create_signal(0);
     let (loading, set_loading) = create_signal(true);
+    let (loading_more, set_loading_more) = create_signal(false);
     let (error, set_error) = create_signal(None::<String>);
 
-    create_effect(move |_| {
+    let load_page = move |cursor: Option<String>| {
         spawn_local(async move {
-            let url = format!("{}/api/v0/gallery", window().location().origin().unwrap());
-            match reqwest::get(&url).await {
-                Ok(response) => match response.json::<Vec<ImageMetadata>>().await {
-                    Ok(mut fetched_images) => {
-                        fetched_images.sort_by(|a, b| a.name.cmp(&b.name));
-                        set_images.set(fetched_images);
-                        set_loading.set(false);
-                    }
-                    Err(e) => {
-                        set_error.set(Some(format!("Failed to parse JSON: {}", e)));
-                        set_loading.set(false);
-                    }
-                },
-                Err(e) => {
-                    set_error.set(Some(format!("Failed to send request: {}", e)));
-                    set_loading.set(false);
+            let query = [("sort", "name".to_string())];
+            match fetch_page::<ImageMetadata>("/api/v0/gallery", &query, cursor.as_deref()).await {
+                Ok(page) => {
+                    set_images.update(|images| images.extend(page.items));
+                    set_next_cursor.set(page.next_cursor);
+                    set_total.set(page.total);
                 }
+                Err(e) => set_error.set(Some(e)),
             }
+            set_loading.set(false);
+            set_loading_more.set(false);
         });
-    });
+    };
+
+    create_effect(move |_| load_page(None));
+
+    let load_more = move |_| {
+        set_loading_more.set(true);
+        load_page(next_cursor.get_untracked());
+    };
 
     view! {
         <div class="min-h-screen flex flex-col">
@@ -66,6 +73,19 @@ pub fn GalleryPage() -> impl IntoView {
                                         </A>
                                     }).collect::<Vec<_>>()}
                                 </div>
+                                <Show when=move || next_cursor.get().is_some()>
+                                    <div class="flex flex-col items-center mt-8 gap-2">
+                                        <button
+                                            class="px-4 py-2 font-mono border-2 border-black rounded hover:bg-black hover:text-white disabled:opacity-50"
+                                            disabled=loading_more
+                                            on:click=load_more>
+                                            {move || if loading_more.get() { "loading..." } else { "> load more" }}
+                                        </button>
+                                        <p class="text-sm text-gray-500">
+                                            {move || format!("{} of {}", images.with(Vec::len), total.get())}
+                                        </p>
+                                    </div>
+                                </Show>
                             }.into_view()
                         }
                     }}
@@ -77,8 +97,8 @@ pub fn GalleryPage() -> impl IntoView {
 
 #[component]
 pub fn GalleryImage() -> impl IntoView {
-    let params = use_params_map();
-    let image_name = move || params.with(|params| params.get("name").cloned().unwrap_or_default());
+    let slug = use_slug("gallery");
+    let image_name = move || slug.get().map(Slug::into_string).unwrap_or_default();
 
     let (image_loaded, set_image_loaded) = create_signal(false);
     let (not_found, set_not_found) = create_signal(false);
@@ -99,7 +119,9 @@ pub fn GalleryImage() -> impl IntoView {
                 <div class="max-w-4xl mx-auto px-4 py-8">
                     <h1 class="text-2xl font-bold mb-4">{image_name}</h1>
                     {move || {
-                        if not_found.get() {
+                        if let Err(err) = slug.get() {
+                            view! { <p class="text-center text-lg text-red-500">"Invalid image name: " {err.to_string()}</p> }.into_view()
+                        } else if not_found.get() {
                             view! { <p class="text-center text-lg text-red-500">"Image not found"</p> }.into_view()
                         } else {
                             view! {
@@ -0,0 +1,47 @@
+use leptos::*;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
+
+/// How many items the listing pages load at a time.
+const PAGE_SIZE: usize = 12;
+
+/// One page of a listing, as served by `/api/v0/blog` and `/api/v0/gallery`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListingPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub total: usize,
+}
+
+/// Fetch the page of the listing at `path` that starts at `cursor`, with `query` holding the sort
+/// order and any filters.
+pub async fn fetch_page<T: DeserializeOwned>(
+    path: &str,
+    query: &[(&str, String)],
+    cursor: Option<&str>,
+) -> Result<ListingPage<T>, String> {
+    let origin = window().location().origin().unwrap();
+    let mut url = Url::parse(&origin)
+        .and_then(|origin| origin.join(path))
+        .map_err(|e| format!("Failed to build URL: {}", e))?;
+
+    {
+        let mut pairs = url.query_pairs_mut();
+        pairs.append_pair("limit", &PAGE_SIZE.to_string());
+        for (key, value) in query {
+            pairs.append_pair(key, value);
+        }
+        if let Some(cursor) = cursor {
+            pairs.append_pair("cursor", cursor);
+        }
+    }
+
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    response
+        .json::<ListingPage<T>>()
+        .await
+        .map_err(|e| format!("Failed to parse JSON: {}", e))
+}
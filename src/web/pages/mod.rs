@@ -3,9 +3,13 @@ mod blog;
 mod error;
 mod gallery;
 mod home;
+mod listing;
+mod search;
+mod slug;
 
 pub use about::AboutPage;
-pub use blog::{BlogPage, BlogPost};
+pub use blog::{BlogPage, BlogPost, BlogTagPage};
 pub use error::ErrorPage;
 pub use gallery::{GalleryImage, GalleryPage};
 pub use home::HomePage;
+pub use search::SearchPage;
@@ -0,0 +1,121 @@
+use leptos::*;
+use leptos_router::*;
+use serde::Deserialize;
+use url::Url;
+
+#[derive(Clone, Debug, Deserialize)]
+struct SearchResult {
+    name: String,
+    title: String,
+    description: String,
+    /// Escaped text with the matched words wrapped in `<mark>`.
+    snippet: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct SearchResults {
+    results: Vec<SearchResult>,
+}
+
+async fn search(query: &str) -> Result<Vec<SearchResult>, String> {
+    let origin = window().location().origin().unwrap();
+    let mut url = Url::parse(&origin)
+        .and_then(|origin| origin.join("/api/v0/search"))
+        .map_err(|e| format!("Failed to build URL: {}", e))?;
+    url.query_pairs_mut().append_pair("q", query);
+
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    response
+        .json::<SearchResults>()
+        .await
+        .map(|results| results.results)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))
+}
+
+#[component]
+pub fn SearchPage() -> impl IntoView {
+    let query_map = use_query_map();
+    let query = create_memo(move |_| {
+        query_map.with(|query_map| query_map.get("q").cloned().unwrap_or_default())
+    });
+
+    let (results, set_results) = create_signal(None::<Vec<SearchResult>>);
+    let (loading, set_loading) = create_signal(false);
+    let (error, set_error) = create_signal(None::<String>);
+
+    create_effect(move |_| {
+        let query = query.get();
+        set_error.set(None);
+        if query.trim().is_empty() {
+            set_results.set(None);
+            return;
+        }
+
+        set_loading.set(true);
+        spawn_local(async move {
+            match search(&query).await {
+                Ok(found) => set_results.set(Some(found)),
+                Err(e) => set_error.set(Some(e)),
+            }
+            set_loading.set(false);
+        });
+    });
+
+    view! {
+        <div class="min-h-screen flex flex-col">
+            <div class="flex-grow overflow-y-auto">
+                <div class="max-w-3xl mx-auto px-4 py-8">
+                    <h1 class="font-mono text-4xl font-bold mb-8">"> search"</h1>
+                    <Form method="GET" action="" class="flex gap-2 mb-8">
+                        <input
+                            type="search"
+                            name="q"
+                            placeholder="search the writing"
+                            prop:value=query
+                            class="flex-grow px-3 py-2 font-mono border-2 border-black rounded"
+                        />
+                        <button
+                            type="submit"
+                            class="px-4 py-2 font-mono border-2 border-black rounded hover:bg-black hover:text-white">
+                            "> go"
+                        </button>
+                    </Form>
+                    {move || {
+                        if loading.get() {
+                            view! {
+                                <div class="flex justify-center items-center h-64">
+                                    <div class="animate-spin rounded-full h-32 w-32 border-t-2 border-b-2 border-gray-900"></div>
+                                </div>
+                            }.into_view()
+                        } else if let Some(err) = error.get() {
+                            view! { <p class="text-center text-red-500">"Error: " {err}</p> }.into_view()
+                        } else {
+                            match results.get() {
+                                None => ().into_view(),
+                                Some(results) if results.is_empty() => view! {
+                                    <p class="text-center text-gray-500">"Nothing matched " {format!("\"{}\"", query.get())}</p>
+                                }.into_view(),
+                                Some(results) => view! {
+                                    <ul class="space-y-6">
+                                        {results.into_iter().map(|result| view! {
+                                            <li class="bg-white rounded-lg overflow-hidden shadow-md hover:shadow-xl
+                                                       transition-all duration-300 ease-in-out">
+                                                <A href=format!("/blog/{}", result.name) class="block p-6">
+                                                    <h2 class="text-2xl font-bold mb-2">{result.title}</h2>
+                                                    <p class="text-gray-600 mb-2">{result.description}</p>
+                                                    <p class="text-sm text-gray-500 [&>mark]:bg-yellow-200" inner_html=result.snippet/>
+                                                </A>
+                                            </li>
+                                        }).collect::<Vec<_>>()}
+                                    </ul>
+                                }.into_view(),
+                            }
+                        }
+                    }}
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,61 @@
+use leptos::*;
+use leptos_router::*;
+
+use crate::slug::{Slug, SlugError};
+
+/// The `:name` parameter of a `/<section>/:name` route, validated as a [`Slug`].
+///
+/// Invalid names are answered with a 400. Case and trailing-slash variants are sent on to the
+/// canonical URL, with a 301 when rendered on the server and by replacing the history entry when
+/// navigating in the browser.
+pub fn use_slug(section: &'static str) -> Memo<Result<Slug, SlugError>> {
+    let params = use_params_map();
+    let location = use_location();
+
+    let slug = create_memo(move |_| {
+        params.with(|params| Slug::canonicalize(params.get("name").map_or("", String::as_str)))
+    });
+    // The query string is carried over, it may hold something like a preview token.
+    let canonical_path = move || {
+        slug.get()
+            .ok()
+            .map(|slug| format!("/{section}/{slug}"))
+            .filter(|canonical_path| *canonical_path != location.pathname.get())
+            .map(|canonical_path| match location.search.get() {
+                search if search.is_empty() => canonical_path,
+                search => format!("{canonical_path}?{search}"),
+            })
+    };
+
+    #[cfg(feature = "ssr")]
+    {
+        use http::{header, HeaderValue, StatusCode};
+        use leptos_axum::ResponseOptions;
+
+        if let Some(response) = use_context::<ResponseOptions>() {
+            if slug.get_untracked().is_err() {
+                response.set_status(StatusCode::BAD_REQUEST);
+            } else if let Some(canonical_path) = untrack(canonical_path) {
+                if let Ok(location) = HeaderValue::from_str(&canonical_path) {
+                    response.set_status(StatusCode::MOVED_PERMANENTLY);
+                    response.insert_header(header::LOCATION, location);
+                }
+            }
+        }
+    }
+
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        if let Some(canonical_path) = canonical_path() {
+            navigate(
+                &canonical_path,
+                NavigateOptions {
+                    replace: true,
+                    ..Default::default()
+                },
+            );
+        }
+    });
+
+    slug
+}